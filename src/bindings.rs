@@ -0,0 +1,499 @@
+/*
+    key bindings
+    named actions mapped to keys and mouse buttons, with defaults that a plain
+    text file can override one action per line, like
+
+        # fly with the arrow keys instead
+        MoveForward = UP
+        MoveCubeUp = I, MOUSE_RIGHT
+
+    an action left with nothing after the = is unbound.
+    a button can only go to two actions that are never read at the same time,
+    like flying and orbiting, anything else is refused
+    keys use the glfw numbering raylib also uses, so the window layer can look
+    them up without this knowing about raylib
+*/
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    ShowBindings,
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    Sprint,
+    ToggleMouseLook,
+    ToggleOrbit,
+    CycleOrbitStyle,
+    FrameSelected,
+    OrbitRotate,
+    OrbitPan,
+    Pick,
+    TogglePickBuffer,
+    GizmoDrag,
+    CycleGizmoMode,
+    ToggleGizmoSpace,
+    GizmoSnap,
+    ToggleDebugDraw,
+    ZoomIn,
+    ZoomOut,
+    ToggleOrthographic,
+    MoveCubeLeft,
+    MoveCubeRight,
+    MoveCubeUp,
+    MoveCubeDown,
+    ToggleOutline,
+    CycleAntialias,
+    CyclePostAntialias,
+    CycleRenderMode,
+    CyclePalette,
+    CycleDither,
+    CycleLight,
+    CycleShadows,
+    ToggleDeferred,
+    TogglePbr,
+    ToggleNormalMap,
+    ToggleGamma,
+    ToggleLut,
+    ToggleVignette,
+    ToggleChromaticAberration,
+    ToggleSharpen,
+    ToggleBlur,
+    ToggleSsao,
+}
+
+// when the viewer reads an action, two that can be read together can't share a button
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Context {
+    Always,
+    Fly,
+    Orbit,
+    // with a gizmo handle under the cursor, where it takes the mouse from the camera
+    Gizmo,
+}
+
+impl Context {
+    pub fn overlaps(&self, other: Context) -> bool {
+        use Context::*;
+        !matches!(
+            (*self, other),
+            (Fly, Orbit) | (Orbit, Fly) | (Gizmo, Fly | Orbit) | (Fly | Orbit, Gizmo)
+        )
+    }
+}
+
+impl Action {
+    pub const ALL: [Action; 47] = [
+        Action::Quit,
+        Action::ShowBindings,
+        Action::MoveForward,
+        Action::MoveBack,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::Sprint,
+        Action::ToggleMouseLook,
+        Action::ToggleOrbit,
+        Action::CycleOrbitStyle,
+        Action::FrameSelected,
+        Action::OrbitRotate,
+        Action::OrbitPan,
+        Action::Pick,
+        Action::TogglePickBuffer,
+        Action::GizmoDrag,
+        Action::CycleGizmoMode,
+        Action::ToggleGizmoSpace,
+        Action::GizmoSnap,
+        Action::ToggleDebugDraw,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::ToggleOrthographic,
+        Action::MoveCubeLeft,
+        Action::MoveCubeRight,
+        Action::MoveCubeUp,
+        Action::MoveCubeDown,
+        Action::ToggleOutline,
+        Action::CycleAntialias,
+        Action::CyclePostAntialias,
+        Action::CycleRenderMode,
+        Action::CyclePalette,
+        Action::CycleDither,
+        Action::CycleLight,
+        Action::CycleShadows,
+        Action::ToggleDeferred,
+        Action::TogglePbr,
+        Action::ToggleNormalMap,
+        Action::ToggleGamma,
+        Action::ToggleLut,
+        Action::ToggleVignette,
+        Action::ToggleChromaticAberration,
+        Action::ToggleSharpen,
+        Action::ToggleBlur,
+        Action::ToggleSsao,
+    ];
+
+    // the name used in bindings files
+    pub fn name(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.iter().copied().find(|a| a.name() == name)
+    }
+
+    pub fn context(&self) -> Context {
+        match self {
+            Action::MoveForward
+            | Action::MoveBack
+            | Action::MoveLeft
+            | Action::MoveRight
+            | Action::MoveUp
+            | Action::MoveDown
+            | Action::Sprint => Context::Fly,
+            Action::CycleOrbitStyle
+            | Action::FrameSelected
+            | Action::OrbitRotate
+            | Action::OrbitPan => Context::Orbit,
+            Action::GizmoDrag => Context::Gizmo,
+            _ => Context::Always,
+        }
+    }
+
+    fn defaults(&self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["ESCAPE"],
+            Action::ShowBindings => &["F1"],
+            Action::MoveForward => &["W"],
+            Action::MoveBack => &["S"],
+            Action::MoveLeft => &["A"],
+            Action::MoveRight => &["D"],
+            Action::MoveUp => &["E"],
+            Action::MoveDown => &["Q"],
+            Action::Sprint => &["LEFT_SHIFT"],
+            Action::ToggleMouseLook => &["TAB"],
+            Action::ToggleOrbit => &["J"],
+            Action::CycleOrbitStyle => &["Y"],
+            Action::FrameSelected => &["F"],
+            Action::OrbitRotate => &["MOUSE_LEFT"],
+            Action::OrbitPan => &["MOUSE_MIDDLE"],
+            Action::Pick => &["MOUSE_RIGHT"],
+            Action::TogglePickBuffer => &["U"],
+            Action::GizmoDrag => &["MOUSE_LEFT"],
+            Action::CycleGizmoMode => &["I"],
+            Action::ToggleGizmoSpace => &["8"],
+            Action::GizmoSnap => &["LEFT_CONTROL"],
+            Action::ToggleDebugDraw => &["0"],
+            Action::ZoomIn => &["Z"],
+            Action::ZoomOut => &["X"],
+            Action::ToggleOrthographic => &["K"],
+            Action::MoveCubeLeft => &["LEFT"],
+            Action::MoveCubeRight => &["RIGHT"],
+            Action::MoveCubeUp => &["UP"],
+            Action::MoveCubeDown => &["DOWN"],
+            Action::ToggleOutline => &["O"],
+            Action::CycleAntialias => &["M"],
+            Action::CyclePostAntialias => &["P"],
+            Action::CycleRenderMode => &["T"],
+            Action::CyclePalette => &["C"],
+            Action::CycleDither => &["V"],
+            Action::CycleLight => &["L"],
+            Action::CycleShadows => &["H"],
+            Action::ToggleDeferred => &["G"],
+            Action::TogglePbr => &["B"],
+            Action::ToggleNormalMap => &["N"],
+            Action::ToggleGamma => &["1"],
+            Action::ToggleLut => &["2"],
+            Action::ToggleVignette => &["3"],
+            Action::ToggleChromaticAberration => &["4"],
+            Action::ToggleSharpen => &["5"],
+            Action::ToggleBlur => &["6"],
+            Action::ToggleSsao => &["7"],
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Button {
+    // glfw key code, letters and digits are their ascii values
+    Key(i32),
+    // 0 left, 1 right, 2 middle
+    Mouse(i32),
+}
+
+// every named key that isn't a letter or a digit
+const KEY_NAMES: [(&str, i32); 32] = [
+    ("SPACE", 32),
+    ("APOSTROPHE", 39),
+    ("COMMA", 44),
+    ("MINUS", 45),
+    ("PERIOD", 46),
+    ("SLASH", 47),
+    ("SEMICOLON", 59),
+    ("EQUAL", 61),
+    ("LEFT_BRACKET", 91),
+    ("BACKSLASH", 92),
+    ("RIGHT_BRACKET", 93),
+    ("GRAVE", 96),
+    ("ESCAPE", 256),
+    ("ENTER", 257),
+    ("TAB", 258),
+    ("BACKSPACE", 259),
+    ("INSERT", 260),
+    ("DELETE", 261),
+    ("RIGHT", 262),
+    ("LEFT", 263),
+    ("DOWN", 264),
+    ("UP", 265),
+    ("PAGE_UP", 266),
+    ("PAGE_DOWN", 267),
+    ("HOME", 268),
+    ("END", 269),
+    ("LEFT_SHIFT", 340),
+    ("LEFT_CONTROL", 341),
+    ("LEFT_ALT", 342),
+    ("RIGHT_SHIFT", 344),
+    ("RIGHT_CONTROL", 345),
+    ("RIGHT_ALT", 346),
+];
+
+const MOUSE_NAMES: [&str; 3] = ["MOUSE_LEFT", "MOUSE_RIGHT", "MOUSE_MIDDLE"];
+
+// f1 through f12 are numbered from here
+const F1: i32 = 290;
+
+impl Button {
+    // case doesn't matter, "w", "F5", "left_shift" and "MOUSE_LEFT" all work
+    pub fn from_name(name: &str) -> Option<Button> {
+        let name = name.to_ascii_uppercase();
+        if let Some(i) = MOUSE_NAMES.iter().position(|&m| m == name) {
+            return Some(Button::Mouse(i as i32));
+        }
+        if let Some((_, code)) = KEY_NAMES.iter().find(|(n, _)| *n == name) {
+            return Some(Button::Key(*code));
+        }
+        let bytes = name.as_bytes();
+        if bytes.len() == 1 && bytes[0].is_ascii_alphanumeric() {
+            return Some(Button::Key(bytes[0] as i32));
+        }
+        match name.strip_prefix('F').and_then(|n| n.parse::<i32>().ok()) {
+            Some(n) if (1..=12).contains(&n) => Some(Button::Key(F1 + n - 1)),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match *self {
+            Button::Mouse(i) => MOUSE_NAMES[i as usize].to_string(),
+            Button::Key(code) => {
+                if let Some((name, _)) = KEY_NAMES.iter().find(|(_, c)| *c == code) {
+                    name.to_string()
+                } else if (F1..F1 + 12).contains(&code) {
+                    format!("F{}", code - F1 + 1)
+                } else {
+                    (code as u8 as char).to_string()
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Bindings {
+    // indexed by action, in the order of Action::ALL
+    buttons: Vec<Vec<Button>>,
+}
+
+impl Bindings {
+    pub fn defaults() -> Bindings {
+        let buttons = Action::ALL
+            .iter()
+            .map(|a| {
+                a.defaults()
+                    .iter()
+                    .filter_map(|n| Button::from_name(n))
+                    .collect()
+            })
+            .collect();
+        Bindings { buttons }
+    }
+
+    pub fn get(&self, action: Action) -> &[Button] {
+        &self.buttons[action as usize]
+    }
+
+    pub fn set(&mut self, action: Action, buttons: Vec<Button>) {
+        self.buttons[action as usize] = buttons;
+    }
+
+    // every pair of actions that share a button and can be read at the same time
+    pub fn conflicts(&self) -> Vec<(Action, Action, Button)> {
+        let mut conflicts = Vec::new();
+        for (i, &a) in Action::ALL.iter().enumerate() {
+            for &b in &Action::ALL[i + 1..] {
+                if !a.context().overlaps(b.context()) {
+                    continue;
+                }
+                for &button in self.get(a) {
+                    if self.get(b).contains(&button) {
+                        conflicts.push((a, b, button));
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+
+    // starts from the defaults, each line replaces one action's buttons
+    pub fn parse(name: &str, text: &str) -> Result<Bindings, String> {
+        let mut bindings = Bindings::defaults();
+        // where each action was last set, to point conflicts at the line that made them
+        let mut lines: Vec<Option<usize>> = vec![None; Action::ALL.len()];
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (action, buttons) = line.split_once('=').ok_or(format!(
+                "{}:{}: expected action = buttons",
+                name,
+                number + 1
+            ))?;
+            let action = Action::from_name(action.trim()).ok_or(format!(
+                "{}:{}: unknown action {}",
+                name,
+                number + 1,
+                action.trim()
+            ))?;
+            let buttons = buttons
+                .split(',')
+                .map(|b| b.trim())
+                .filter(|b| !b.is_empty())
+                .map(|b| {
+                    Button::from_name(b).ok_or(format!(
+                        "{}:{}: unknown button {}",
+                        name,
+                        number + 1,
+                        b
+                    ))
+                })
+                .collect::<Result<Vec<Button>, String>>()?;
+            bindings.set(action, buttons);
+            lines[action as usize] = Some(number + 1);
+        }
+        if let Some((a, b, button)) = bindings.conflicts().first() {
+            let line = lines[*a as usize].max(lines[*b as usize]);
+            let at = line
+                .map(|l| format!("{}:{}", name, l))
+                .unwrap_or(name.to_string());
+            return Err(format!(
+                "{}: {} is bound to both {} and {}",
+                at,
+                button.name(),
+                a.name(),
+                b.name()
+            ));
+        }
+        Ok(bindings)
+    }
+
+    pub fn load(path: &str) -> Result<Bindings, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Bindings::parse(path, &text)
+    }
+
+    // one "Action = BUTTON, BUTTON" line per action, the same format parse reads
+    pub fn lines(&self) -> Vec<String> {
+        Action::ALL
+            .iter()
+            .map(|&a| {
+                let names: Vec<String> = self.get(a).iter().map(|b| b.name()).collect();
+                format!("{} = {}", a.name(), names.join(", "))
+            })
+            .collect()
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        Bindings::defaults()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_have_no_conflicts() {
+        assert!(Bindings::defaults().conflicts().is_empty());
+        for action in Action::ALL {
+            assert_eq!(Action::from_name(&action.name()), Some(action));
+        }
+    }
+
+    #[test]
+    fn button_names_round_trip() {
+        for name in ["W", "7", "F5", "LEFT_SHIFT", "MOUSE_MIDDLE", "ESCAPE"] {
+            assert_eq!(Button::from_name(name).unwrap().name(), name);
+        }
+        assert_eq!(
+            Button::from_name("left_shift"),
+            Button::from_name("LEFT_SHIFT")
+        );
+        assert!(Button::from_name("F13").is_none());
+        assert!(Button::from_name("NOPE").is_none());
+    }
+
+    #[test]
+    fn parse_overrides_and_unbinds() {
+        let text = "# comment\n\nMoveForward = UP, semicolon  # trailing\nMoveCubeUp = \n";
+        let bindings = Bindings::parse("test", text).unwrap();
+        assert_eq!(
+            bindings.get(Action::MoveForward),
+            &[Button::Key(265), Button::Key(59)]
+        );
+        assert!(bindings.get(Action::MoveCubeUp).is_empty());
+        // untouched actions keep their defaults
+        assert_eq!(
+            bindings.get(Action::Quit),
+            Bindings::defaults().get(Action::Quit)
+        );
+    }
+
+    #[test]
+    fn parse_reads_what_lines_writes() {
+        let bindings = Bindings::defaults();
+        let text = bindings.lines().join("\n");
+        let parsed = Bindings::parse("test", &text).unwrap();
+        assert_eq!(parsed.lines(), bindings.lines());
+    }
+
+    #[test]
+    fn parse_errors_name_the_line() {
+        let err = Bindings::parse("b.txt", "\nMoveForward UP").unwrap_err();
+        assert!(err.starts_with("b.txt:2:"), "{}", err);
+        let err = Bindings::parse("b.txt", "Fly = W").unwrap_err();
+        assert!(err.contains("unknown action"), "{}", err);
+        let err = Bindings::parse("b.txt", "Quit = NOPE").unwrap_err();
+        assert!(err.contains("unknown button"), "{}", err);
+    }
+
+    #[test]
+    fn parse_refuses_conflicts() {
+        // flying and orbiting never read at the same time, so they can share
+        assert!(Bindings::parse("b", "MoveBack = S, F").is_ok());
+        let err = Bindings::parse("b", "\nMoveCubeUp = W").unwrap_err();
+        assert!(err.starts_with("b:2:"), "{}", err);
+        assert!(
+            err.contains("MoveForward") && err.contains("MoveCubeUp"),
+            "{}",
+            err
+        );
+        // moving the other one out of the way first is fine
+        assert!(Bindings::parse("b", "MoveCubeUp = W\nMoveForward = R").is_ok());
+    }
+}
@@ -0,0 +1,338 @@
+/*
+    camera
+    a position and orientation plus a perspective or orthographic projection
+    looks down its own -z with +y up, like opengl
+    plus fly and orbit controllers that turn input into camera motion, the window
+    layer fills in the input so this stays free of raylib
+*/
+
+use crate::mm::{Mat3, Mat4, Quat, Ray, Sphere, Vec2, Vec3};
+
+#[derive(Debug, Copy, Clone)]
+pub struct Camera {
+    pub position: Vec3,
+    pub rotation: Quat,
+    // vertical field of view in radians, used when perspective
+    pub fov: f32,
+    // world units from the bottom to the top of the view, used when orthographic
+    pub ortho_height: f32,
+    pub orthographic: bool,
+    // width over height
+    pub aspect: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Camera {
+    pub fn perspective(fov: f32, aspect: f32, near: f32, far: f32) -> Camera {
+        Camera {
+            position: Vec3::zero(),
+            rotation: Quat::identity(),
+            fov,
+            ortho_height: 10.0,
+            orthographic: false,
+            aspect,
+            near,
+            far,
+        }
+    }
+
+    pub fn orthographic(height: f32, aspect: f32, near: f32, far: f32) -> Camera {
+        Camera {
+            ortho_height: height,
+            orthographic: true,
+            ..Camera::perspective(std::f32::consts::FRAC_PI_2, aspect, near, far)
+        }
+    }
+
+    pub fn forward(&self) -> Vec3 {
+        self.rotation.rotate(Vec3::new(0.0, 0.0, -1.0))
+    }
+
+    pub fn right(&self) -> Vec3 {
+        self.rotation.rotate(Vec3::new(1.0, 0.0, 0.0))
+    }
+
+    pub fn up(&self) -> Vec3 {
+        self.rotation.rotate(Vec3::new(0.0, 1.0, 0.0))
+    }
+
+    // turn to face target, up only has to be roughly right
+    pub fn look_at(&mut self, target: Vec3, up: Vec3) {
+        let f = (target - self.position).normalize();
+        let r = f.cross(&up).normalize();
+        let u = r.cross(&f);
+        self.rotation = Quat::from_mat3(&Mat3::from_cols(r, u, -f)).normalize();
+    }
+
+    // take position and orientation from a world matrix, like a scene node's
+    // any scale in it is dropped
+    pub fn set_transform(&mut self, world: &Mat4) {
+        self.position = Vec3::new(world.m[0][3], world.m[1][3], world.m[2][3]);
+        let m = Mat3::from_mat4(world);
+        let col = |c: usize| Vec3::new(m.m[0][c], m.m[1][c], m.m[2][c]).normalize();
+        self.rotation = Quat::from_mat3(&Mat3::from_cols(col(0), col(1), col(2))).normalize();
+    }
+
+    pub fn view(&self) -> Mat4 {
+        Mat4::look_at(self.position, self.position + self.forward(), self.up())
+    }
+
+    pub fn projection(&self) -> Mat4 {
+        if self.orthographic {
+            let h = self.ortho_height * 0.5;
+            let w = h * self.aspect;
+            Mat4::orthographic(-w, w, -h, h, self.near, self.far)
+        } else {
+            Mat4::perspective(self.fov, self.aspect, self.near, self.far)
+        }
+    }
+
+    pub fn view_proj(&self) -> Mat4 {
+        self.projection() * self.view()
+    }
+
+    // ray through a pixel position on a width by height screen, y down
+    // starts on the near plane so it works the same for both projections
+    pub fn screen_to_ray(&self, screen: Vec2, width: f32, height: f32) -> Ray {
+        let inv = self.view_proj().inverse().unwrap_or_else(Mat4::identity);
+        let ndc = Vec2::new(screen.x / width * 2.0 - 1.0, 1.0 - screen.y / height * 2.0);
+        let unproject = |z: f32| {
+            let p = inv * Vec3::new(ndc.x, ndc.y, z).extend(1.0);
+            p.xyz() / p.w
+        };
+        let near = unproject(-1.0);
+        let far = unproject(1.0);
+        Ray::new(near, far - near)
+    }
+}
+
+// what a controller gets from the keyboard and mouse for one frame
+#[derive(Debug, Copy, Clone)]
+pub struct ControlInput {
+    // mouse movement in pixels since the last frame, y down
+    pub look: Vec2,
+    // -1..1 along the camera's right, world up and the camera's forward
+    pub movement: Vec3,
+    pub sprint: bool,
+}
+
+impl Default for ControlInput {
+    fn default() -> ControlInput {
+        ControlInput {
+            look: Vec2::new(0.0, 0.0),
+            movement: Vec3::zero(),
+            sprint: false,
+        }
+    }
+}
+
+// first person fly camera, yaw around world +y then pitch around the camera's x
+#[derive(Debug, Copy, Clone)]
+pub struct FlyController {
+    // radians, zero looks down -z
+    pub yaw: f32,
+    // radians, positive looks up, kept short of straight up or down
+    pub pitch: f32,
+    // world units per second
+    pub speed: f32,
+    pub sprint_multiplier: f32,
+    // radians per pixel of mouse movement
+    pub sensitivity: f32,
+}
+
+impl FlyController {
+    // picks up yaw and pitch from wherever the camera is already facing
+    pub fn new(camera: &Camera) -> FlyController {
+        let f = camera.forward();
+        FlyController {
+            yaw: (-f.x).atan2(-f.z),
+            pitch: f.y.clamp(-1.0, 1.0).asin(),
+            speed: 6.0,
+            sprint_multiplier: 3.0,
+            sensitivity: 0.003,
+        }
+    }
+
+    pub fn rotation(&self) -> Quat {
+        Quat::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), self.yaw)
+            * Quat::from_axis_angle(Vec3::new(1.0, 0.0, 0.0), self.pitch)
+    }
+
+    // dt is the frame time in seconds so speed doesn't depend on frame rate
+    pub fn update(&mut self, camera: &mut Camera, input: &ControlInput, dt: f32) {
+        let limit = 89f32.to_radians();
+        self.yaw -= input.look.x * self.sensitivity;
+        self.pitch = (self.pitch - input.look.y * self.sensitivity).clamp(-limit, limit);
+        self.yaw %= std::f32::consts::TAU;
+        camera.rotation = self.rotation();
+
+        let mut dir = camera.right() * input.movement.x
+            + Vec3::new(0.0, input.movement.y, 0.0)
+            + camera.forward() * input.movement.z;
+        // diagonals don't get to be faster
+        if dir.dot(&dir) > 1.0 {
+            dir = dir.normalize();
+        }
+        let mut speed = self.speed;
+        if input.sprint {
+            speed *= self.sprint_multiplier;
+        }
+        camera.position += dir * speed * dt;
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OrbitMode {
+    // yaw around world up and pitch over the top, the horizon stays level
+    Turntable,
+    // shoemake's arcball, dragging rolls a virtual ball under the cursor
+    Arcball,
+}
+
+// what the orbit controller gets from the mouse for one frame
+#[derive(Debug, Copy, Clone)]
+pub struct OrbitInput {
+    // pixels, y down, on a screen this big
+    pub cursor: Vec2,
+    pub screen: Vec2,
+    // buttons held for turning and panning
+    pub rotate: bool,
+    pub pan: bool,
+    // wheel clicks, positive moves in
+    pub scroll: f32,
+}
+
+// circles a target point at some distance, for looking a model over
+#[derive(Debug, Copy, Clone)]
+pub struct OrbitController {
+    pub target: Vec3,
+    pub distance: f32,
+    // which way the camera faces, it sits back along its own +z from the target
+    pub rotation: Quat,
+    pub mode: OrbitMode,
+    // radians per pixel in turntable mode
+    pub sensitivity: f32,
+    // how much one wheel click changes the distance
+    pub dolly_step: f32,
+    pub min_distance: f32,
+    // where the cursor was last frame while a button was held
+    last_cursor: Option<Vec2>,
+}
+
+impl OrbitController {
+    // keeps the camera where it is and turns it to face target
+    pub fn new(camera: &Camera, target: Vec3) -> OrbitController {
+        let mut facing = *camera;
+        facing.look_at(target, Vec3::new(0.0, 1.0, 0.0));
+        OrbitController {
+            target,
+            distance: (camera.position - target).length(),
+            rotation: facing.rotation,
+            mode: OrbitMode::Turntable,
+            sensitivity: 0.01,
+            dolly_step: 0.9,
+            min_distance: 0.01,
+            last_cursor: None,
+        }
+    }
+
+    pub fn update(&mut self, camera: &mut Camera, input: &OrbitInput) {
+        if input.rotate || input.pan {
+            if let Some(last) = self.last_cursor {
+                if input.rotate {
+                    self.rotate(last, input.cursor, input.screen);
+                } else {
+                    self.pan(camera, input.cursor - last, input.screen);
+                }
+            }
+            self.last_cursor = Some(input.cursor);
+        } else {
+            self.last_cursor = None;
+        }
+
+        if input.scroll != 0.0 {
+            let factor = self.dolly_step.powf(input.scroll);
+            self.distance = (self.distance * factor).max(self.min_distance);
+            // an orthographic camera doesn't get bigger moving in, so zoom it instead
+            camera.ortho_height *= factor;
+        }
+        self.apply(camera);
+    }
+
+    // put the camera where the controller says it is
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.rotation = self.rotation;
+        camera.position =
+            self.target + self.rotation.rotate(Vec3::new(0.0, 0.0, 1.0)) * self.distance;
+    }
+
+    // center on the sphere and back off until all of it is in view
+    pub fn frame(&mut self, camera: &mut Camera, bounds: &Sphere) {
+        let half_y = camera.fov * 0.5;
+        let half_x = (half_y.tan() * camera.aspect).atan();
+        let radius = bounds.radius.max(1e-3);
+        self.target = bounds.center;
+        self.distance = (radius / half_y.min(half_x).sin()).max(self.min_distance);
+        camera.ortho_height = 2.0 * radius * (1.0f32).max(1.0 / camera.aspect);
+        self.apply(camera);
+    }
+
+    fn rotate(&mut self, from: Vec2, to: Vec2, screen: Vec2) {
+        match self.mode {
+            OrbitMode::Turntable => {
+                let delta = to - from;
+                let yaw =
+                    Quat::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), -delta.x * self.sensitivity);
+                let pitch =
+                    Quat::from_axis_angle(Vec3::new(1.0, 0.0, 0.0), -delta.y * self.sensitivity);
+                let turned = (yaw * self.rotation).normalize();
+                let pitched = (turned * pitch).normalize();
+                // don't let it tip over the pole and come up upside down
+                self.rotation = if pitched.rotate(Vec3::new(0.0, 1.0, 0.0)).y > 0.0 {
+                    pitched
+                } else {
+                    turned
+                };
+            }
+            OrbitMode::Arcball => {
+                let a = arcball_point(from, screen);
+                let b = arcball_point(to, screen);
+                // shoemake's quaternion between the two points turns by twice their angle,
+                // which is what makes a drag across the ball spin it all the way round
+                let axis = a.cross(&b);
+                let spin = Quat::new(axis.x, axis.y, axis.z, a.dot(&b));
+                // the ball turns in view space, the camera goes the other way around it
+                self.rotation = (self.rotation * spin.conjugate()).normalize();
+            }
+        }
+    }
+
+    // slide the target across the view so the point under the cursor follows it
+    fn pan(&mut self, camera: &Camera, delta: Vec2, screen: Vec2) {
+        let view_height = if camera.orthographic {
+            camera.ortho_height
+        } else {
+            2.0 * self.distance * (camera.fov * 0.5).tan()
+        };
+        let per_pixel = view_height / screen.y;
+        let right = self.rotation.rotate(Vec3::new(1.0, 0.0, 0.0));
+        let up = self.rotation.rotate(Vec3::new(0.0, 1.0, 0.0));
+        self.target += (up * delta.y - right * delta.x) * per_pixel;
+    }
+}
+
+// a cursor position on the unit ball filling the middle of the screen, in view space
+// off the ball it lands on the rim
+fn arcball_point(cursor: Vec2, screen: Vec2) -> Vec3 {
+    let size = screen.x.min(screen.y);
+    let x = (2.0 * cursor.x - screen.x) / size;
+    let y = (screen.y - 2.0 * cursor.y) / size;
+    let d = x * x + y * y;
+    if d > 1.0 {
+        let len = d.sqrt();
+        Vec3::new(x / len, y / len, 0.0)
+    } else {
+        Vec3::new(x, y, (1.0 - d).sqrt())
+    }
+}
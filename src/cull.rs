@@ -0,0 +1,45 @@
+/*
+    view frustum culling
+    whole objects get tested against the camera's frustum by their bounds
+    before any of their vertices are transformed, and the results get counted
+*/
+
+use crate::mm::{Aabb, Frustum, Mat4, Sphere};
+
+pub struct Culler {
+    pub frustum: Frustum,
+    pub drawn: usize,
+    pub culled: usize,
+}
+
+impl Culler {
+    // world space planes of the camera, counts start at zero
+    pub fn new(view_proj: &Mat4) -> Culler {
+        Culler {
+            frustum: Frustum::from_matrix(view_proj),
+            drawn: 0,
+            culled: 0,
+        }
+    }
+
+    fn count(&mut self, visible: bool) -> bool {
+        if visible {
+            self.drawn += 1;
+        } else {
+            self.culled += 1;
+        }
+        visible
+    }
+
+    // local bounds moved into world space by the model matrix
+    pub fn aabb(&mut self, bounds: &Aabb, model: &Mat4) -> bool {
+        let visible = self.frustum.intersects_aabb(&bounds.transform(model));
+        self.count(visible)
+    }
+
+    // cheaper than the box, but looser for long thin things
+    pub fn sphere(&mut self, bounds: &Sphere, model: &Mat4) -> bool {
+        let visible = self.frustum.intersects_sphere(&bounds.transform(model));
+        self.count(visible)
+    }
+}
@@ -0,0 +1,312 @@
+/*
+    debug drawing
+    queue lines, points and the shapes built from them from anywhere during a frame,
+    then draw them all at once through the rasterizer and clear for the next frame.
+    whatever is queued while depth_test is off shows through the scene, for axes
+    and the like that should never get lost behind things.
+    lines can fade out along their length, screen door style through a bayer
+    matrix since the overlay they go into replaces pixels rather than blending
+*/
+
+use crate::framebuffer::Framebuffer;
+use crate::mesh::Mesh;
+use crate::mm::{Aabb, Mat3, Mat4, Sphere, Vec2, Vec3, Vec4};
+use crate::noise::bayer_matrix;
+use crate::raster::{draw_line, draw_point, Fragment, Varying, Vertex};
+use crate::state::{Cull, RenderState, StencilState};
+
+// segments in a circle
+const CIRCLE_SEGMENTS: usize = 24;
+
+// every tenth grid line is brighter and reaches all the way out
+const MAJOR_EVERY: i32 = 10;
+
+// how many cells out the minor grid lines fade to nothing
+const MINOR_REACH: f32 = 25.0;
+
+// pieces each grid line is cut into so the fade follows distance from the eye
+const GRID_SEGMENTS: usize = 16;
+
+// side of the bayer matrix fading is dithered with
+const FADE_MATRIX: usize = 4;
+
+// pulls lines a little towards the camera, so ones lying on a surface win against it
+const DEPTH_BIAS: f32 = -0.0002;
+
+#[derive(Debug, Copy, Clone)]
+struct Line {
+    a: Vec3,
+    b: Vec3,
+    color: Vec3,
+    // how much of the line is drawn at each end, 1 is solid
+    fade: [f32; 2],
+    depth_test: bool,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Point {
+    position: Vec3,
+    size: f32,
+    color: Vec3,
+    depth_test: bool,
+}
+
+pub struct DebugDraw {
+    // applies to everything queued until it changes
+    pub depth_test: bool,
+    lines: Vec<Line>,
+    points: Vec<Point>,
+}
+
+impl DebugDraw {
+    pub fn new() -> DebugDraw {
+        DebugDraw {
+            depth_test: true,
+            lines: Vec::new(),
+            points: Vec::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.points.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.points.is_empty()
+    }
+
+    pub fn line(&mut self, a: Vec3, b: Vec3, color: Vec3) {
+        self.faded_line(a, b, color, [1.0, 1.0]);
+    }
+
+    // a line that thins out from one end to the other, fade is 1 for solid, 0 for gone
+    pub fn faded_line(&mut self, a: Vec3, b: Vec3, color: Vec3, fade: [f32; 2]) {
+        if fade[0] <= 0.0 && fade[1] <= 0.0 {
+            return;
+        }
+        self.lines.push(Line {
+            a,
+            b,
+            color,
+            fade,
+            depth_test: self.depth_test,
+        });
+    }
+
+    // size is in pixels
+    pub fn point(&mut self, position: Vec3, size: f32, color: Vec3) {
+        self.points.push(Point {
+            position,
+            size,
+            color,
+            depth_test: self.depth_test,
+        });
+    }
+
+    // corners in the order a box's bits pick min or max on x, y and z
+    fn box_edges(&mut self, corners: [Vec3; 8], color: Vec3) {
+        for i in 0..8 {
+            for bit in [1, 2, 4] {
+                if i & bit == 0 {
+                    self.line(corners[i], corners[i | bit], color);
+                }
+            }
+        }
+    }
+
+    pub fn aabb(&mut self, aabb: &Aabb, color: Vec3) {
+        self.oriented_box(aabb, &Mat4::identity(), color);
+    }
+
+    // a local space box carried along by a model matrix
+    pub fn oriented_box(&mut self, aabb: &Aabb, model: &Mat4, color: Vec3) {
+        let corners = std::array::from_fn(|i| {
+            let p = Vec3::new(
+                if i & 1 == 0 { aabb.min.x } else { aabb.max.x },
+                if i & 2 == 0 { aabb.min.y } else { aabb.max.y },
+                if i & 4 == 0 { aabb.min.z } else { aabb.max.z },
+            );
+            (*model * p.extend(1.0)).xyz()
+        });
+        self.box_edges(corners, color);
+    }
+
+    pub fn circle(&mut self, center: Vec3, normal: Vec3, radius: f32, color: Vec3) {
+        let normal = normal.normalize();
+        // any direction not along the normal works as a start for the basis
+        let helper = if normal.y.abs() < 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let u = normal.cross(&helper).normalize() * radius;
+        let v = normal.cross(&u);
+        let at = |i: usize| {
+            let a = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+            center + u * a.cos() + v * a.sin()
+        };
+        for i in 0..CIRCLE_SEGMENTS {
+            self.line(at(i), at(i + 1), color);
+        }
+    }
+
+    // a ring around each axis
+    pub fn sphere(&mut self, sphere: &Sphere, color: Vec3) {
+        for axis in 0..3 {
+            let mut normal = Vec3::zero();
+            normal[axis] = 1.0;
+            self.circle(sphere.center, normal, sphere.radius, color);
+        }
+    }
+
+    // the volume a view projection matrix sees, by taking the clip space cube back out
+    pub fn frustum(&mut self, view_proj: &Mat4, color: Vec3) {
+        let inverse = match view_proj.inverse() {
+            Some(inverse) => inverse,
+            None => return,
+        };
+        let corners = std::array::from_fn(|i| {
+            let ndc = Vec4::new(
+                if i & 1 == 0 { -1.0 } else { 1.0 },
+                if i & 2 == 0 { -1.0 } else { 1.0 },
+                if i & 4 == 0 { -1.0 } else { 1.0 },
+                1.0,
+            );
+            let p = inverse * ndc;
+            p.xyz() / p.w
+        });
+        self.box_edges(corners, color);
+    }
+
+    // x red, y green, z blue, from the matrix's origin along its own axes
+    pub fn axes(&mut self, transform: &Mat4, length: f32) {
+        let origin = (*transform * Vec4::new(0.0, 0.0, 0.0, 1.0)).xyz();
+        for axis in 0..3 {
+            let mut direction = Vec4::new(0.0, 0.0, 0.0, 0.0);
+            direction[axis] = 1.0;
+            let tip = (*transform * direction).xyz().normalize() * length;
+            let mut color = Vec3::zero();
+            color[axis] = 1.0;
+            self.line(origin, origin + tip, color);
+        }
+    }
+
+    // a ground grid on the y = height plane that looks endless from eye: it reaches as
+    // far as the far plane can see and fades out with distance, so there is no edge to
+    // spot. minor lines fade out sooner than the major ones so they don't turn to mush.
+    // the lines sit still in the world, only how far they reach follows the eye.
+    // the world x and z axes are drawn red and blue where they cross it
+    pub fn grid(&mut self, eye: Vec3, height: f32, spacing: f32, far: f32, color: Vec3) {
+        let above = eye.y - height;
+        if spacing <= 0.0 || above.abs() >= far {
+            return;
+        }
+        let reach = (far * far - above * above).sqrt();
+        let minor_reach = reach.min(spacing * MINOR_REACH);
+        // fades with the distance along the ground from the eye
+        let fade = |p: Vec3, reach: f32| {
+            let d = ((p.x - eye.x).powi(2) + (p.z - eye.z).powi(2)).sqrt();
+            (1.0 - d / reach).clamp(0.0, 1.0).powi(2)
+        };
+        for (axis, across) in [(0, 2), (2, 0)] {
+            // red for the x axis, blue for the z axis, each a line along itself
+            let mut axis_color = Vec3::zero();
+            axis_color[axis] = 1.0;
+            let center = (eye[across] / spacing).round() as i32;
+            let count = (reach / spacing).ceil() as i32;
+            for i in center - count..=center + count {
+                let major = i % MAJOR_EVERY == 0;
+                let (line_color, line_reach) = match (i, major) {
+                    (0, _) => (axis_color, reach),
+                    (_, true) => (color * 2.0, reach),
+                    _ => (color, minor_reach),
+                };
+                // the line's half length inside the circle it can be seen in
+                let offset = i as f32 * spacing - eye[across];
+                if offset.abs() >= line_reach {
+                    continue;
+                }
+                let half = (line_reach * line_reach - offset * offset).sqrt();
+                let at = |t: f32| {
+                    let mut p = Vec3::new(0.0, height, 0.0);
+                    p[across] = i as f32 * spacing;
+                    p[axis] = eye[axis] - half + 2.0 * half * t;
+                    p
+                };
+                for s in 0..GRID_SEGMENTS {
+                    let a = at(s as f32 / GRID_SEGMENTS as f32);
+                    let b = at((s + 1) as f32 / GRID_SEGMENTS as f32);
+                    let fades = [fade(a, line_reach), fade(b, line_reach)];
+                    self.faded_line(a, b, line_color, fades);
+                }
+            }
+        }
+    }
+
+    // a line out of every vertex along its world space normal
+    pub fn normals(&mut self, mesh: &Mesh, model: &Mat4, length: f32, color: Vec3) {
+        let normal_matrix = Mat3::normal_matrix(model);
+        for (p, n) in mesh.positions.iter().zip(&mesh.normals) {
+            let p = (*model * p.extend(1.0)).xyz();
+            let n = (normal_matrix * *n).normalize();
+            self.line(p, p + n * length, color);
+        }
+    }
+
+    // rasterize everything queued, nothing writes depth so the order doesn't matter.
+    // drawn pixels are marked in the stencil plane for Framebuffer::overlay
+    pub fn draw(&self, fb: &mut Framebuffer, view_proj: &Mat4) {
+        let state = |depth_test: bool| RenderState {
+            cull: Cull::None,
+            depth_test,
+            depth_write: false,
+            stencil: StencilState::write(1),
+            depth_bias: DEPTH_BIAS,
+            ..RenderState::default()
+        };
+        // the fade rides along in the uv, nothing else reads it here
+        let vertex = |p: Vec3, color: Vec3, fade: f32| {
+            let mut varying = Varying::new(color);
+            varying.uv = Vec2::new(fade, 0.0);
+            Vertex {
+                position: *view_proj * p.extend(1.0),
+                varying,
+            }
+        };
+        let thresholds = bayer_matrix(FADE_MATRIX);
+        let mut shade = |frag: &Fragment| {
+            let threshold = thresholds[(frag.y % FADE_MATRIX) * FADE_MATRIX + frag.x % FADE_MATRIX];
+            if frag.varying.uv.x < threshold {
+                return None;
+            }
+            Some(frag.varying.color)
+        };
+        for line in &self.lines {
+            draw_line(
+                fb,
+                &state(line.depth_test),
+                &[
+                    vertex(line.a, line.color, line.fade[0]),
+                    vertex(line.b, line.color, line.fade[1]),
+                ],
+                &mut shade,
+            );
+        }
+        for point in &self.points {
+            draw_point(
+                fb,
+                &state(point.depth_test),
+                &vertex(point.position, point.color, 1.0),
+                point.size,
+                &mut shade,
+            );
+        }
+    }
+}
+
+impl Default for DebugDraw {
+    fn default() -> DebugDraw {
+        DebugDraw::new()
+    }
+}
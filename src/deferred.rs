@@ -0,0 +1,274 @@
+/*
+    deferred shading
+    the geometry pass rasterizes surface attributes into a g-buffer, then the
+    lighting pass runs once per pixel. lights are binned into screen tiles first
+    so each pixel only looks at the handful of lights that can reach it
+*/
+
+use crate::framebuffer::Framebuffer;
+use crate::light::{Light, LightKind};
+use crate::mm::{Mat4, Vec3};
+use crate::raster::{draw_indexed, Fragment, Vertex};
+use crate::state::RenderState;
+
+// blinn-phong parameters stored per pixel
+#[derive(Debug, Copy, Clone)]
+pub struct Material {
+    pub specular: f32,
+    pub shininess: f32,
+}
+
+impl Default for Material {
+    fn default() -> Material {
+        Material {
+            specular: 0.0,
+            shininess: 16.0,
+        }
+    }
+}
+
+// what the geometry pass shader hands back instead of a color
+#[derive(Debug, Copy, Clone)]
+pub struct Surface {
+    pub albedo: Vec3,
+    pub normal: Vec3,
+    pub material: Material,
+}
+
+pub struct GBuffer {
+    pub width: usize,
+    pub height: usize,
+    // depth and stencil for the geometry pass, its color plane goes unused
+    pub target: Framebuffer,
+    pub albedo: Vec<Vec3>,
+    pub normal: Vec<Vec3>,
+    pub position: Vec<Vec3>,
+    pub material: Vec<Material>,
+}
+
+impl GBuffer {
+    pub fn new(width: usize, height: usize) -> GBuffer {
+        let n = width * height;
+        GBuffer {
+            width,
+            height,
+            target: Framebuffer::new(width, height),
+            albedo: vec![Vec3::zero(); n],
+            normal: vec![Vec3::zero(); n],
+            position: vec![Vec3::zero(); n],
+            material: vec![Material::default(); n],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.target.clear(Vec3::zero());
+    }
+
+    // pixels still at the far plane have nothing to light
+    pub fn covered(&self, i: usize) -> bool {
+        self.target.depth[i] < 1.0
+    }
+
+    // geometry pass, the depth test makes sure the closest surface's attributes win
+    pub fn draw<F>(
+        &mut self,
+        state: &RenderState,
+        vertices: &[Vertex],
+        indices: &[[usize; 3]],
+        surface: &mut F,
+    ) where
+        F: FnMut(&Fragment) -> Option<Surface>,
+    {
+        let width = self.width;
+        let state = RenderState {
+            color_write: false,
+            ..*state
+        };
+        draw_indexed(
+            &mut self.target,
+            &state,
+            vertices,
+            indices,
+            &mut |frag: &Fragment| {
+                let s = surface(frag)?;
+                let i = frag.y * width + frag.x;
+                self.albedo[i] = s.albedo;
+                self.normal[i] = s.normal.normalize();
+                self.position[i] = frag.varying.world;
+                self.material[i] = s.material;
+                Some(Vec3::zero())
+            },
+        );
+    }
+
+    // lighting pass, writes the color plane of out which must be the same size
+    // depth and normals get copied over too so screen space effects still work
+    pub fn shade(
+        &self,
+        out: &mut Framebuffer,
+        lights: &[Light],
+        tiles: &LightTiles,
+        eye: Vec3,
+        ambient: Vec3,
+    ) {
+        for ty in 0..tiles.tiles_y {
+            for tx in 0..tiles.tiles_x {
+                let list = &tiles.lists[ty * tiles.tiles_x + tx];
+                let x_end = ((tx + 1) * tiles.tile_size).min(self.width);
+                let y_end = ((ty + 1) * tiles.tile_size).min(self.height);
+                for y in ty * tiles.tile_size..y_end {
+                    for x in tx * tiles.tile_size..x_end {
+                        let i = y * self.width + x;
+                        if !self.covered(i) {
+                            continue;
+                        }
+                        let mut color = self.albedo[i] * ambient;
+                        for &l in list {
+                            color += self.light(i, &lights[l], eye);
+                        }
+                        let pixel = out.index(x, y);
+                        out.color[pixel] = color;
+                        out.normal[pixel] = self.normal[i];
+                        for s in 0..out.samples() {
+                            let j = out.sample_index(pixel, s);
+                            out.depth[j] = self.target.depth[i];
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // blinn-phong for one light at one pixel
+    fn light(&self, i: usize, light: &Light, eye: Vec3) -> Vec3 {
+        let (l, radiance) = light.incident(self.position[i]);
+        let n = self.normal[i];
+        let n_dot_l = n.dot(&l);
+        // tiles are coarse, plenty of pixels in them are still out of range
+        if n_dot_l <= 0.0 || radiance.dot(&radiance) == 0.0 {
+            return Vec3::zero();
+        }
+        let material = self.material[i];
+        let v = (eye - self.position[i]).normalize();
+        let h = (l + v).normalize();
+        let specular = material.specular * n.dot(&h).max(0.0).powf(material.shininess);
+        (self.albedo[i] * n_dot_l + Vec3::one() * specular) * radiance
+    }
+}
+
+// which lights touch each screen tile
+pub struct LightTiles {
+    pub tile_size: usize,
+    pub tiles_x: usize,
+    pub tiles_y: usize,
+    pub lists: Vec<Vec<usize>>,
+}
+
+impl LightTiles {
+    // bin lights by the screen rectangle and depth range of their bounding box,
+    // tested against the depth range of what the g-buffer actually holds per tile
+    pub fn build(
+        gbuffer: &GBuffer,
+        lights: &[Light],
+        view_proj: &Mat4,
+        tile_size: usize,
+    ) -> LightTiles {
+        // a zero tile size would divide by zero, one pixel tiles is the closest that works
+        let tile_size = tile_size.max(1);
+        let tiles_x = gbuffer.width.div_ceil(tile_size);
+        let tiles_y = gbuffer.height.div_ceil(tile_size);
+
+        // min and max depth per tile, empty tiles keep min above max
+        let mut depth_range = vec![(1.0f32, 0.0f32); tiles_x * tiles_y];
+        for y in 0..gbuffer.height {
+            for x in 0..gbuffer.width {
+                let i = y * gbuffer.width + x;
+                if gbuffer.covered(i) {
+                    let z = gbuffer.target.depth[i];
+                    let range = &mut depth_range[(y / tile_size) * tiles_x + x / tile_size];
+                    range.0 = range.0.min(z);
+                    range.1 = range.1.max(z);
+                }
+            }
+        }
+
+        let mut lists = vec![Vec::new(); tiles_x * tiles_y];
+        let (width, height) = (gbuffer.width as f32, gbuffer.height as f32);
+        for (l, light) in lights.iter().enumerate() {
+            let (center, radius) = match light.kind {
+                LightKind::Directional { .. } => {
+                    for list in lists.iter_mut() {
+                        list.push(l);
+                    }
+                    continue;
+                }
+                LightKind::Point { position, range } => (position, range),
+                LightKind::Spot {
+                    position, range, ..
+                } => (position, range),
+            };
+
+            // screen bounds of the box around the light's sphere of influence
+            let mut min = Vec3::new(f32::MAX, f32::MAX, f32::MAX);
+            let mut max = Vec3::new(f32::MIN, f32::MIN, f32::MIN);
+            let mut behind = false;
+            for corner in 0..8 {
+                let offset = Vec3::new(
+                    if corner & 1 == 0 { -radius } else { radius },
+                    if corner & 2 == 0 { -radius } else { radius },
+                    if corner & 4 == 0 { -radius } else { radius },
+                );
+                let clip = *view_proj * (center + offset).extend(1.0);
+                if clip.w <= 1e-4 {
+                    behind = true;
+                    break;
+                }
+                let ndc = clip.xyz() / clip.w;
+                let p = Vec3::new(
+                    (ndc.x * 0.5 + 0.5) * width,
+                    (0.5 - ndc.y * 0.5) * height,
+                    ndc.z * 0.5 + 0.5,
+                );
+                for a in 0..3 {
+                    min[a] = min[a].min(p[a]);
+                    max[a] = max[a].max(p[a]);
+                }
+            }
+            // a box crossing the camera plane could cover anything
+            if behind {
+                min = Vec3::new(0.0, 0.0, 0.0);
+                max = Vec3::new(width, height, 1.0);
+            }
+            if max.x < 0.0 || max.y < 0.0 || min.x >= width || min.y >= height {
+                continue;
+            }
+
+            let tx0 = (min.x.max(0.0) as usize / tile_size).min(tiles_x - 1);
+            let ty0 = (min.y.max(0.0) as usize / tile_size).min(tiles_y - 1);
+            let tx1 = (max.x.max(0.0) as usize / tile_size).min(tiles_x - 1);
+            let ty1 = (max.y.max(0.0) as usize / tile_size).min(tiles_y - 1);
+            for ty in ty0..=ty1 {
+                for tx in tx0..=tx1 {
+                    let t = ty * tiles_x + tx;
+                    let (near, far) = depth_range[t];
+                    if near <= far && min.z <= far && max.z >= near {
+                        lists[t].push(l);
+                    }
+                }
+            }
+        }
+
+        LightTiles {
+            tile_size,
+            tiles_x,
+            tiles_y,
+            lists,
+        }
+    }
+
+    // average number of lights per tile, handy for seeing how well culling works
+    pub fn average(&self) -> f32 {
+        let total: usize = self.lists.iter().map(|l| l.len()).sum();
+        total as f32 / self.lists.len().max(1) as f32
+    }
+}
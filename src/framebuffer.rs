@@ -0,0 +1,219 @@
+/*
+    cpu framebuffer
+    color, depth and stencil planes the rasterizer draws into
+    colors are linear floats, converted to bytes only when uploading to raylib
+    with antialiasing on, depth, stencil and color are kept per sample
+    and resolve() averages the samples down into the color plane
+    the coverage plane counts how many of 8 subsamples each pixel's last
+    triangle covered, n64 style, so the vi filter can smooth edges with it
+*/
+
+use crate::mm::Vec3;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Antialias {
+    None,
+    // coverage and depth per sample, shading once per pixel
+    Msaa(usize),
+    // everything per sample, reference quality but n times the shading cost
+    Ssaa(usize),
+}
+
+impl Antialias {
+    pub fn samples(&self) -> usize {
+        match self {
+            Antialias::None => 1,
+            Antialias::Msaa(n) | Antialias::Ssaa(n) => *n,
+        }
+    }
+
+    // the same mode with its count brought down to one there's a sample pattern for,
+    // a single sample is no antialiasing at all
+    pub fn supported(&self) -> Antialias {
+        match *self {
+            Antialias::None => Antialias::None,
+            Antialias::Msaa(n) => match supported_samples(n) {
+                1 => Antialias::None,
+                n => Antialias::Msaa(n),
+            },
+            Antialias::Ssaa(n) => match supported_samples(n) {
+                1 => Antialias::None,
+                n => Antialias::Ssaa(n),
+            },
+        }
+    }
+}
+
+// standard d3d / gl sample positions, offsets from the pixel center in 1/16ths
+const PATTERN_1X: [(i8, i8); 1] = [(0, 0)];
+const PATTERN_2X: [(i8, i8); 2] = [(4, 4), (-4, -4)];
+const PATTERN_4X: [(i8, i8); 4] = [(-2, -6), (6, -2), (-6, 2), (2, 6)];
+const PATTERN_8X: [(i8, i8); 8] = [
+    (1, -3),
+    (-1, 3),
+    (5, 1),
+    (-3, -5),
+    (-5, 5),
+    (-7, -1),
+    (3, 7),
+    (7, -7),
+];
+
+// the most samples there's a pattern for without going over the count asked for
+pub fn supported_samples(samples: usize) -> usize {
+    [8, 4, 2].into_iter().find(|&n| n <= samples).unwrap_or(1)
+}
+
+// counts without a pattern get the one supported_samples picks
+pub fn sample_positions(samples: usize) -> Vec<(f32, f32)> {
+    let pattern: &[(i8, i8)] = match supported_samples(samples) {
+        8 => &PATTERN_8X,
+        4 => &PATTERN_4X,
+        2 => &PATTERN_2X,
+        _ => &PATTERN_1X,
+    };
+    pattern
+        .iter()
+        .map(|&(x, y)| (x as f32 / 16.0, y as f32 / 16.0))
+        .collect()
+}
+
+// coverage of a pixel that is completely inside a triangle
+pub const FULL_COVERAGE: u8 = 8;
+
+pub struct Framebuffer {
+    pub width: usize,
+    pub height: usize,
+    pub antialias: Antialias,
+    pub color: Vec<Vec3>,
+    pub sample_color: Vec<Vec3>,
+    pub depth: Vec<f32>,
+    pub stencil: Vec<u8>,
+    pub coverage: Vec<u8>,
+    // normal of whatever was drawn last in each pixel, for screen space effects
+    pub normal: Vec<Vec3>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Framebuffer {
+        Framebuffer::with_antialias(width, height, Antialias::None)
+    }
+
+    // unsupported sample counts fall back to the nearest one below, see Antialias::supported
+    pub fn with_antialias(width: usize, height: usize, antialias: Antialias) -> Framebuffer {
+        let antialias = antialias.supported();
+        let size = width * height;
+        let samples = antialias.samples();
+        Framebuffer {
+            width,
+            height,
+            antialias,
+            color: vec![Vec3::zero(); size],
+            sample_color: if samples > 1 {
+                vec![Vec3::zero(); size * samples]
+            } else {
+                Vec::new()
+            },
+            depth: vec![1.0; size * samples],
+            stencil: vec![0; size * samples],
+            coverage: vec![FULL_COVERAGE; size],
+            normal: vec![Vec3::zero(); size],
+        }
+    }
+
+    pub fn set_antialias(&mut self, antialias: Antialias) {
+        *self = Framebuffer::with_antialias(self.width, self.height, antialias);
+    }
+
+    pub fn samples(&self) -> usize {
+        self.antialias.samples()
+    }
+
+    pub fn index(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
+
+    // index into the per sample depth, stencil and sample color planes
+    pub fn sample_index(&self, pixel: usize, sample: usize) -> usize {
+        pixel * self.samples() + sample
+    }
+
+    pub fn write_color(&mut self, pixel: usize, sample: usize, color: Vec3) {
+        if self.samples() > 1 {
+            let i = self.sample_index(pixel, sample);
+            self.sample_color[i] = color;
+        } else {
+            self.color[pixel] = color;
+        }
+    }
+
+    pub fn clear_color(&mut self, color: Vec3) {
+        self.color.fill(color);
+        self.sample_color.fill(color);
+    }
+
+    pub fn clear_depth(&mut self, depth: f32) {
+        self.depth.fill(depth);
+    }
+
+    pub fn clear_stencil(&mut self, value: u8) {
+        self.stencil.fill(value);
+    }
+
+    pub fn clear(&mut self, color: Vec3) {
+        self.clear_color(color);
+        self.clear_depth(1.0);
+        self.clear_stencil(0);
+        self.coverage.fill(FULL_COVERAGE);
+        self.normal.fill(Vec3::zero());
+    }
+
+    // box filter the samples of each pixel into the color plane
+    pub fn resolve(&mut self) {
+        let samples = self.samples();
+        if samples == 1 {
+            return;
+        }
+        for (pixel, chunk) in self.sample_color.chunks(samples).enumerate() {
+            let mut sum = Vec3::zero();
+            for c in chunk {
+                sum += *c;
+            }
+            self.color[pixel] = sum / samples as f32;
+        }
+    }
+
+    // copy in the pixels a same sized buffer marked in its stencil plane
+    // for things like gizmos that go over the finished, post processed frame
+    pub fn overlay(&mut self, top: &Framebuffer) {
+        for pixel in 0..self.color.len().min(top.color.len()) {
+            if top.stencil[top.sample_index(pixel, 0)] != 0 {
+                self.color[pixel] = top.color[pixel];
+            }
+        }
+    }
+
+    // take another buffer's depth, its first sample per pixel when it has several,
+    // so an overlay can still be hidden by the scene underneath
+    pub fn copy_depth(&mut self, from: &Framebuffer) {
+        for pixel in 0..(self.width * self.height).min(from.width * from.height) {
+            let depth = from.depth[from.sample_index(pixel, 0)];
+            for s in 0..self.samples() {
+                let i = self.sample_index(pixel, s);
+                self.depth[i] = depth;
+            }
+        }
+    }
+
+    // pack the color plane as rgba8, top row first, ready for update_texture
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let mut pixels = Vec::with_capacity(self.color.len() * 4);
+        for c in &self.color {
+            pixels.push((c.x.clamp(0.0, 1.0) * 255.0) as u8);
+            pixels.push((c.y.clamp(0.0, 1.0) * 255.0) as u8);
+            pixels.push((c.z.clamp(0.0, 1.0) * 255.0) as u8);
+            pixels.push(255);
+        }
+        pixels
+    }
+}
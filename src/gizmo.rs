@@ -0,0 +1,529 @@
+/*
+    transform gizmos
+    arrows, rings and boxes drawn over a scene node that move, turn and scale it
+    when dragged. axis handles constrain to one axis, the squares between two axes
+    to their plane and the middle to the view plane or to uniform scale.
+    the handles keep the same size on screen however far away the node is
+*/
+
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::mm::{Mat4, Plane, Quat, Ray, Sphere, Vec3};
+use crate::raster::{draw_indexed, Fragment, Varying, Vertex};
+use crate::scene::{NodeId, Scene, Transform};
+use crate::state::{Cull, RenderState, StencilState};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GizmoMode {
+    Translate,
+    Rotate,
+    Scale,
+}
+
+// which axes the handles follow, scaling always goes along the node's own axes
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GizmoSpace {
+    World,
+    Local,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Handle {
+    // along one axis, or around it when rotating
+    Axis(usize),
+    // the plane across this axis, so Plane(1) slides over xz
+    Plane(usize),
+    // the view plane when moving, all axes at once when scaling
+    Center,
+}
+
+// where a drag started, everything is measured against this
+#[derive(Debug, Copy, Clone)]
+struct Drag {
+    node: NodeId,
+    handle: Handle,
+    start: Transform,
+    // world into the parent's space
+    parent_inverse: Mat4,
+    // just the parent's world rotation, for directions
+    parent_rotation: Quat,
+    origin: Vec3,
+    axes: [Vec3; 3],
+    size: f32,
+    // the plane the cursor ray gets intersected with and where it first hit it
+    plane: Plane,
+    grab: Vec3,
+    right: Vec3,
+}
+
+pub struct Gizmo {
+    pub mode: GizmoMode,
+    pub space: GizmoSpace,
+    // round to the steps below while on
+    pub snap: bool,
+    pub translate_step: f32,
+    // radians
+    pub rotate_step: f32,
+    pub scale_step: f32,
+    // fraction of the view height the handles reach
+    pub size: f32,
+    // what the cursor is over, as of the last hover
+    pub hovered: Option<Handle>,
+    drag: Option<Drag>,
+}
+
+const AXIS_COLORS: [Vec3; 3] = [
+    Vec3 {
+        x: 0.9,
+        y: 0.2,
+        z: 0.2,
+    },
+    Vec3 {
+        x: 0.2,
+        y: 0.9,
+        z: 0.2,
+    },
+    Vec3 {
+        x: 0.2,
+        y: 0.4,
+        z: 1.0,
+    },
+];
+const CENTER_COLOR: Vec3 = Vec3 {
+    x: 0.8,
+    y: 0.8,
+    z: 0.8,
+};
+const ACTIVE_COLOR: Vec3 = Vec3 {
+    x: 1.0,
+    y: 0.9,
+    z: 0.1,
+};
+
+// handle proportions, all in units of the gizmo's size
+const PICK_RADIUS: f32 = 0.1;
+const PLANE_MIN: f32 = 0.25;
+const PLANE_MAX: f32 = 0.45;
+const CENTER_RADIUS: f32 = 0.15;
+const RING_SEGMENTS: usize = 32;
+
+fn snap(value: f32, step: f32, on: bool) -> f32 {
+    if on && step > 0.0 {
+        (value / step).round() * step
+    } else {
+        value
+    }
+}
+
+// shortest distance between a ray and the segment a b, and how far along the ray that is
+fn ray_segment(ray: &Ray, a: Vec3, b: Vec3) -> (f32, f32) {
+    let e = b - a;
+    let w = ray.origin - a;
+    let ee = e.dot(&e);
+    let de = ray.direction.dot(&e);
+    let denom = ee - de * de;
+    let s = if denom.abs() < 1e-12 {
+        0.0
+    } else {
+        ((e.dot(&w) - de * ray.direction.dot(&w)) / denom).clamp(0.0, 1.0)
+    };
+    let q = a + e * s;
+    let t = (q - ray.origin).dot(&ray.direction).max(0.0);
+    ((ray.at(t) - q).length(), t)
+}
+
+fn hit_plane(ray: &Ray, plane: &Plane) -> Option<Vec3> {
+    ray.intersect_plane(plane).map(|t| ray.at(t))
+}
+
+impl Gizmo {
+    pub fn new(mode: GizmoMode) -> Gizmo {
+        Gizmo {
+            mode,
+            space: GizmoSpace::World,
+            snap: false,
+            translate_step: 0.5,
+            rotate_step: 15f32.to_radians(),
+            scale_step: 0.25,
+            size: 0.15,
+            hovered: None,
+            drag: None,
+        }
+    }
+
+    pub fn dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    // the handle being dragged, or else the one under the cursor
+    pub fn active(&self) -> Option<Handle> {
+        self.drag.map(|d| d.handle).or(self.hovered)
+    }
+
+    // origin and axes the handles hang off for this node
+    fn frame(&self, scene: &Scene, node: NodeId) -> (Vec3, [Vec3; 3]) {
+        let world = scene.world(node);
+        let origin = Vec3::new(world.m[0][3], world.m[1][3], world.m[2][3]);
+        let col = |c: usize| Vec3::new(world.m[0][c], world.m[1][c], world.m[2][c]).normalize();
+        let axes = if self.space == GizmoSpace::Local || self.mode == GizmoMode::Scale {
+            [col(0), col(1), col(2)]
+        } else {
+            [
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                Vec3::new(0.0, 0.0, 1.0),
+            ]
+        };
+        (origin, axes)
+    }
+
+    // world length of the handles at origin, so they stay the same size on screen
+    fn world_size(&self, camera: &Camera, origin: Vec3) -> f32 {
+        let view_height = if camera.orthographic {
+            camera.ortho_height
+        } else {
+            let depth = (origin - camera.position)
+                .dot(&camera.forward())
+                .max(camera.near);
+            2.0 * depth * (camera.fov * 0.5).tan()
+        };
+        view_height * self.size
+    }
+
+    // the closest handle along the ray, remembered in hovered
+    pub fn hover(
+        &mut self,
+        scene: &Scene,
+        node: NodeId,
+        camera: &Camera,
+        ray: &Ray,
+    ) -> Option<Handle> {
+        let (origin, axes) = self.frame(scene, node);
+        let size = self.world_size(camera, origin);
+        let mut best: Option<(f32, Handle)> = None;
+        let mut consider = |t: f32, handle: Handle| {
+            if best.is_none_or(|(b, _)| t < b) {
+                best = Some((t, handle));
+            }
+        };
+
+        for (i, &axis) in axes.iter().enumerate() {
+            let plane = Plane::from_point_normal(origin, axis);
+            match self.mode {
+                GizmoMode::Rotate => {
+                    if let Some(t) = ray.intersect_plane(&plane) {
+                        let r = (ray.at(t) - origin).length();
+                        if (r - size).abs() < size * PICK_RADIUS {
+                            consider(t, Handle::Axis(i));
+                        }
+                    }
+                }
+                GizmoMode::Translate | GizmoMode::Scale => {
+                    let a = origin + axis * (size * CENTER_RADIUS);
+                    let b = origin + axis * (size * 1.1);
+                    let (distance, t) = ray_segment(ray, a, b);
+                    if distance < size * PICK_RADIUS {
+                        consider(t, Handle::Axis(i));
+                    }
+                    if let Some(t) = ray.intersect_plane(&plane) {
+                        let p = ray.at(t) - origin;
+                        let u = p.dot(&axes[(i + 1) % 3]) / size;
+                        let v = p.dot(&axes[(i + 2) % 3]) / size;
+                        let inside = |x: f32| (PLANE_MIN..=PLANE_MAX).contains(&x);
+                        if inside(u) && inside(v) {
+                            consider(t, Handle::Plane(i));
+                        }
+                    }
+                }
+            }
+        }
+        if self.mode != GizmoMode::Rotate {
+            let center = Sphere::new(origin, size * CENTER_RADIUS);
+            if let Some(t) = ray.intersect_sphere(&center) {
+                consider(t, Handle::Center);
+            }
+        }
+
+        self.hovered = best.map(|(_, h)| h);
+        self.hovered
+    }
+
+    // grabs whatever handle is under the ray, false if there isn't one
+    pub fn begin(&mut self, scene: &Scene, node: NodeId, camera: &Camera, ray: &Ray) -> bool {
+        let handle = match self.hover(scene, node, camera, ray) {
+            Some(handle) => handle,
+            None => return false,
+        };
+        let (origin, axes) = self.frame(scene, node);
+        let view = camera.forward();
+        // slide along an axis on the plane through it that faces the camera best
+        let normal = match (self.mode, handle) {
+            (GizmoMode::Rotate, Handle::Axis(i)) | (_, Handle::Plane(i)) => axes[i],
+            (_, Handle::Axis(i)) => {
+                let n = view - axes[i] * view.dot(&axes[i]);
+                if n.dot(&n) < 1e-6 {
+                    return false;
+                }
+                n
+            }
+            (_, Handle::Center) => view,
+        };
+        let plane = Plane::from_point_normal(origin, normal);
+        let grab = match hit_plane(ray, &plane) {
+            Some(grab) => grab,
+            None => return false,
+        };
+        let (parent_inverse, parent_rotation) = match scene.node(node).parent() {
+            Some(parent) => (
+                scene.world(parent).inverse().unwrap_or_else(Mat4::identity),
+                scene.world_rotation(parent),
+            ),
+            None => (Mat4::identity(), Quat::identity()),
+        };
+        self.drag = Some(Drag {
+            node,
+            handle,
+            start: *scene.node(node).transform(),
+            parent_inverse,
+            parent_rotation,
+            origin,
+            axes,
+            size: self.world_size(camera, origin),
+            plane,
+            grab,
+            right: camera.right(),
+        });
+        true
+    }
+
+    // follows the cursor ray, setting the node's transform from where the drag started
+    pub fn drag(&mut self, scene: &mut Scene, ray: &Ray) {
+        let drag = match self.drag {
+            Some(drag) => drag,
+            None => return,
+        };
+        let hit = match hit_plane(ray, &drag.plane) {
+            Some(hit) => hit,
+            None => return,
+        };
+        let mut transform = drag.start;
+        match self.mode {
+            GizmoMode::Translate => {
+                let moved = hit - drag.grab;
+                let free: &[usize] = match drag.handle {
+                    Handle::Axis(i) => &[i],
+                    Handle::Plane(i) => &[(i + 1) % 3, (i + 2) % 3],
+                    Handle::Center => &[0, 1, 2],
+                };
+                // snapping lands the position itself on the grid along each free axis,
+                // not the distance moved, so an off grid node gets pulled onto it
+                let mut world = drag.origin;
+                for &i in free {
+                    let axis = drag.axes[i];
+                    let to = (drag.origin + moved).dot(&axis);
+                    world += axis * (snap(to, self.translate_step, self.snap) - world.dot(&axis));
+                }
+                transform.position = (drag.parent_inverse * world.extend(1.0)).xyz();
+            }
+            GizmoMode::Rotate => {
+                let axis = match drag.handle {
+                    Handle::Axis(i) => drag.axes[i],
+                    _ => return,
+                };
+                let a = drag.grab - drag.origin;
+                let b = hit - drag.origin;
+                let angle = a.cross(&b).dot(&axis).atan2(a.dot(&b));
+                let angle = snap(angle, self.rotate_step, self.snap);
+                // turn about the same world axis, seen from the parent
+                let local_axis = drag.parent_rotation.conjugate().rotate(axis).normalize();
+                transform.rotation =
+                    (Quat::from_axis_angle(local_axis, angle) * drag.start.rotation).normalize();
+            }
+            GizmoMode::Scale => {
+                let from = drag.grab - drag.origin;
+                let to = hit - drag.origin;
+                let ratio = |a: f32, b: f32| if a.abs() < 1e-6 { 1.0 } else { b / a };
+                let scaled = |i: usize, r: f32| {
+                    snap(drag.start.scale[i] * r, self.scale_step, self.snap).max(0.01)
+                };
+                match drag.handle {
+                    Handle::Axis(i) => {
+                        let r = ratio(from.dot(&drag.axes[i]), to.dot(&drag.axes[i]));
+                        transform.scale[i] = scaled(i, r);
+                    }
+                    Handle::Plane(i) => {
+                        let r = ratio(from.length(), to.length());
+                        for j in [(i + 1) % 3, (i + 2) % 3] {
+                            transform.scale[j] = scaled(j, r);
+                        }
+                    }
+                    Handle::Center => {
+                        let r = 1.0 + (hit - drag.grab).dot(&drag.right) / drag.size;
+                        for j in 0..3 {
+                            transform.scale[j] = scaled(j, r);
+                        }
+                    }
+                }
+            }
+        }
+        scene.set_transform(drag.node, transform);
+    }
+
+    pub fn end(&mut self) {
+        self.drag = None;
+    }
+
+    // rasterizes the handles for node into fb, depth tested against each other only,
+    // so fb is best a cleared overlay that goes over the finished frame.
+    // the handles mark the stencil plane for Framebuffer::overlay
+    pub fn draw(&self, fb: &mut Framebuffer, scene: &Scene, node: NodeId, camera: &Camera) {
+        let (origin, axes) = self.frame(scene, node);
+        let size = self.world_size(camera, origin);
+        let active = self.active();
+        let color = |handle: Handle, base: Vec3| {
+            if active == Some(handle) {
+                ACTIVE_COLOR
+            } else {
+                base
+            }
+        };
+        let thin = size * 0.02;
+        let mut shapes = Shapes::default();
+
+        for (i, &axis) in axes.iter().enumerate() {
+            let u = axes[(i + 1) % 3];
+            let v = axes[(i + 2) % 3];
+            let c = color(Handle::Axis(i), AXIS_COLORS[i]);
+            match self.mode {
+                GizmoMode::Rotate => {
+                    let point = |k: usize| {
+                        let a = k as f32 / RING_SEGMENTS as f32 * std::f32::consts::TAU;
+                        origin + (u * a.cos() + v * a.sin()) * size
+                    };
+                    for k in 0..RING_SEGMENTS {
+                        shapes.bar(point(k), point(k + 1), thin, c);
+                    }
+                }
+                GizmoMode::Translate | GizmoMode::Scale => {
+                    let tip = origin + axis * size;
+                    shapes.bar(origin, tip, thin, c);
+                    if self.mode == GizmoMode::Translate {
+                        shapes.cone(tip, axis * (size * 0.2), size * 0.07, c);
+                    } else {
+                        shapes.cube(tip, size * 0.06, c);
+                    }
+                    let corner = |a: f32, b: f32| origin + (u * a + v * b) * size;
+                    shapes.quad(
+                        [
+                            corner(PLANE_MIN, PLANE_MIN),
+                            corner(PLANE_MAX, PLANE_MIN),
+                            corner(PLANE_MAX, PLANE_MAX),
+                            corner(PLANE_MIN, PLANE_MAX),
+                        ],
+                        color(Handle::Plane(i), AXIS_COLORS[i] * 0.7),
+                    );
+                }
+            }
+        }
+        if self.mode != GizmoMode::Rotate {
+            shapes.cube(
+                origin,
+                size * CENTER_RADIUS * 0.6,
+                color(Handle::Center, CENTER_COLOR),
+            );
+        }
+
+        let view_proj = camera.view_proj();
+        let vertices: Vec<Vertex> = shapes
+            .points
+            .iter()
+            .map(|&(p, c)| Vertex {
+                position: view_proj * p.extend(1.0),
+                varying: Varying::new(c),
+            })
+            .collect();
+        let state = RenderState {
+            cull: Cull::None,
+            stencil: StencilState::write(1),
+            ..RenderState::default()
+        };
+        draw_indexed(
+            fb,
+            &state,
+            &vertices,
+            &shapes.indices,
+            &mut |frag: &Fragment| Some(frag.varying.color),
+        );
+    }
+}
+
+// flat colored triangles for the handles
+#[derive(Default)]
+struct Shapes {
+    points: Vec<(Vec3, Vec3)>,
+    indices: Vec<[usize; 3]>,
+}
+
+impl Shapes {
+    fn quad(&mut self, corners: [Vec3; 4], color: Vec3) {
+        let base = self.points.len();
+        self.points.extend(corners.iter().map(|&p| (p, color)));
+        self.indices.push([base, base + 1, base + 2]);
+        self.indices.push([base, base + 2, base + 3]);
+    }
+
+    // square beam from a to b
+    fn bar(&mut self, a: Vec3, b: Vec3, half_width: f32, color: Vec3) {
+        let dir = (b - a).normalize();
+        let side = if dir.x.abs() < 0.9 {
+            Vec3::new(1.0, 0.0, 0.0)
+        } else {
+            Vec3::new(0.0, 1.0, 0.0)
+        };
+        let u = dir.cross(&side).normalize() * half_width;
+        let v = dir.cross(&u).normalize() * half_width;
+        let ring = [u + v, u - v, -u - v, -u + v];
+        for k in 0..4 {
+            let (p, q) = (ring[k], ring[(k + 1) % 4]);
+            self.quad([a + p, a + q, b + q, b + p], color);
+        }
+    }
+
+    // four sided cone from base out along length
+    fn cone(&mut self, base: Vec3, length: Vec3, radius: f32, color: Vec3) {
+        let tip = base + length;
+        let dir = length.normalize();
+        let side = if dir.x.abs() < 0.9 {
+            Vec3::new(1.0, 0.0, 0.0)
+        } else {
+            Vec3::new(0.0, 1.0, 0.0)
+        };
+        let u = dir.cross(&side).normalize() * radius;
+        let v = dir.cross(&u).normalize() * radius;
+        let ring = [base + u, base + v, base - u, base - v];
+        for k in 0..4 {
+            let i = self.points.len();
+            self.points.push((ring[k], color));
+            self.points.push((ring[(k + 1) % 4], color));
+            self.points.push((tip, color));
+            self.indices.push([i, i + 1, i + 2]);
+        }
+        self.quad(ring, color);
+    }
+
+    fn cube(&mut self, center: Vec3, half: f32, color: Vec3) {
+        let x = Vec3::new(half, 0.0, 0.0);
+        self.bar(center - x, center + x, half, color);
+        let ends = |s: f32| {
+            let c = center + x * s;
+            [
+                c + Vec3::new(0.0, half, half),
+                c + Vec3::new(0.0, -half, half),
+                c + Vec3::new(0.0, -half, -half),
+                c + Vec3::new(0.0, half, -half),
+            ]
+        };
+        self.quad(ends(1.0), color);
+        self.quad(ends(-1.0), color);
+    }
+}
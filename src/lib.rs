@@ -0,0 +1,26 @@
+/*
+    software renderer
+    math, framebuffer and rasterizer live here, main.rs is the raylib viewer
+*/
+
+pub mod bindings;
+pub mod camera;
+pub mod cull;
+pub mod debug;
+pub mod deferred;
+pub mod framebuffer;
+pub mod gizmo;
+pub mod light;
+pub mod mesh;
+pub mod mm;
+pub mod noise;
+pub mod palette;
+pub mod pbr;
+pub mod pick;
+pub mod post;
+pub mod ps1;
+pub mod raster;
+pub mod scene;
+pub mod shadow;
+pub mod state;
+pub mod texture;
@@ -0,0 +1,116 @@
+/*
+    lights
+    directional, point and spot lights, and how much of each reaches a surface
+    before shadows are taken into account
+*/
+
+use crate::mm::Vec3;
+
+#[derive(Debug, Copy, Clone)]
+pub enum LightKind {
+    // direction is the way the light travels, not towards the light
+    Directional {
+        direction: Vec3,
+    },
+    Point {
+        position: Vec3,
+        range: f32,
+    },
+    // angle is the full width of the cone in radians
+    Spot {
+        position: Vec3,
+        direction: Vec3,
+        angle: f32,
+        range: f32,
+    },
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Light {
+    pub kind: LightKind,
+    pub color: Vec3,
+    pub intensity: f32,
+}
+
+// inverse square falloff windowed so it reaches exactly zero at the range
+fn attenuation(distance: f32, range: f32) -> f32 {
+    let r = distance / range;
+    let window = (1.0 - r * r * r * r).clamp(0.0, 1.0);
+    window * window / (distance * distance + 1.0)
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+impl Light {
+    pub fn directional(direction: Vec3, color: Vec3, intensity: f32) -> Light {
+        Light {
+            kind: LightKind::Directional {
+                direction: direction.normalize(),
+            },
+            color,
+            intensity,
+        }
+    }
+
+    pub fn point(position: Vec3, range: f32, color: Vec3, intensity: f32) -> Light {
+        Light {
+            kind: LightKind::Point { position, range },
+            color,
+            intensity,
+        }
+    }
+
+    pub fn spot(
+        position: Vec3,
+        direction: Vec3,
+        angle: f32,
+        range: f32,
+        color: Vec3,
+        intensity: f32,
+    ) -> Light {
+        Light {
+            kind: LightKind::Spot {
+                position,
+                direction: direction.normalize(),
+                angle,
+                range,
+            },
+            color,
+            intensity,
+        }
+    }
+
+    // unit vector from the surface towards the light, and the light arriving there
+    pub fn incident(&self, world: Vec3) -> (Vec3, Vec3) {
+        let radiance = self.color * self.intensity;
+        match self.kind {
+            LightKind::Directional { direction } => (-direction, radiance),
+            LightKind::Point { position, range } => {
+                let to_light = position - world;
+                let distance = to_light.length();
+                (
+                    to_light / distance.max(1e-6),
+                    radiance * attenuation(distance, range),
+                )
+            }
+            LightKind::Spot {
+                position,
+                direction,
+                angle,
+                range,
+            } => {
+                let to_light = position - world;
+                let distance = to_light.length();
+                let l = to_light / distance.max(1e-6);
+                // soft edge over the outer fifth of the cone
+                let outer = (angle * 0.5).cos();
+                let inner = (angle * 0.4).cos();
+                let cone = smoothstep(outer, inner, (-l).dot(&direction));
+                (l, radiance * (attenuation(distance, range) * cone))
+            }
+        }
+    }
+}
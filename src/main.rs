@@ -1,55 +1,92 @@
-mod mm;
+use raylib::core::input::key_from_i32;
 use raylib::prelude::*;
+use software_renderer_rust::bindings::{Action, Bindings, Button};
+use software_renderer_rust::camera::{
+    Camera, ControlInput, FlyController, OrbitController, OrbitInput, OrbitMode,
+};
+use software_renderer_rust::cull::Culler;
+use software_renderer_rust::debug::DebugDraw;
+use software_renderer_rust::deferred::{GBuffer, LightTiles, Material, Surface};
+use software_renderer_rust::framebuffer::Antialias;
+use software_renderer_rust::gizmo::{Gizmo, GizmoMode, GizmoSpace};
+use software_renderer_rust::light::{Light, LightKind};
+use software_renderer_rust::mesh::Mesh;
+use software_renderer_rust::noise::Rng;
+use software_renderer_rust::palette::Palette;
+use software_renderer_rust::pbr::{self, Environment, PbrMaterial};
+use software_renderer_rust::pick::{self, Hit, IdBuffer};
+use software_renderer_rust::post::{
+    blur::Blur, chromatic::ChromaticAberration, fxaa::Fxaa, gamma::Gamma, lut::Lut,
+    quantize::BayerSize, quantize::Dither, quantize::Quantize, rgb555::Rgb555, sharpen::Sharpen,
+    smaa::Smaa, ssao::Ssao, vi::ViFilter, vignette::Vignette, PostChain,
+};
+use software_renderer_rust::scene::{Attachment, NodeId, Scene, Transform};
+use software_renderer_rust::shadow::{CubeShadowMap, ShadowFilter, ShadowMap};
+use software_renderer_rust::texture::{Filter, Texture};
+use software_renderer_rust::{framebuffer, mm, ps1, raster, state};
 
-fn make_cube() -> Vec<mm::Vec3> {
-    let mut cube: Vec<mm::Vec3> = Vec::new();
-    cube.push(mm::Vec3 {
-        x: -1.0,
-        y: 1.0,
-        z: 1.0,
-    });
-    cube.push(mm::Vec3 {
-        x: 1.0,
-        y: 1.0,
-        z: 1.0,
-    });
-    cube.push(mm::Vec3 {
-        x: 1.0,
-        y: -1.0,
-        z: 1.0,
-    });
-    cube.push(mm::Vec3 {
-        x: -1.0,
-        y: -1.0,
-        z: 1.0,
-    });
-    cube.push(mm::Vec3 {
-        x: -1.0,
-        y: 1.0,
-        z: -1.0,
-    });
-    cube.push(mm::Vec3 {
-        x: 1.0,
-        y: 1.0,
-        z: -1.0,
-    });
-    cube.push(mm::Vec3 {
-        x: 1.0,
-        y: -1.0,
-        z: -1.0,
-    });
-    cube.push(mm::Vec3 {
-        x: -1.0,
-        y: -1.0,
-        z: -1.0,
-    });
-    // multiply the verts by 0.5
-    for vert in &mut cube {
-        vert.x *= 0.5;
-        vert.y *= 0.5;
-        vert.z *= 0.5;
+// t cycles through these
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum RenderMode {
+    Modern,
+    Ps1,
+    N64,
+}
+
+// everything but ps1 mode has a depth buffer to sort things out
+fn draw_mesh<F>(
+    fb: &mut framebuffer::Framebuffer,
+    state: &state::RenderState,
+    mode: RenderMode,
+    vertices: &[raster::Vertex],
+    indices: &[[usize; 3]],
+    shade: &mut F,
+) where
+    F: FnMut(&raster::Fragment) -> Option<mm::Vec3>,
+{
+    if mode == RenderMode::Ps1 {
+        ps1::draw_ordered(fb, state, vertices, indices, 256, shade);
+    } else {
+        raster::draw_indexed(fb, state, vertices, indices, shade);
+    }
+}
+
+fn button_down(rl: &RaylibHandle, button: Button, pressed: bool) -> bool {
+    match button {
+        Button::Key(code) => match key_from_i32(code) {
+            Some(key) if pressed => rl.is_key_pressed(key),
+            Some(key) => rl.is_key_down(key),
+            None => false,
+        },
+        Button::Mouse(index) => {
+            let mouse = match index {
+                0 => MouseButton::MOUSE_LEFT_BUTTON,
+                1 => MouseButton::MOUSE_RIGHT_BUTTON,
+                _ => MouseButton::MOUSE_MIDDLE_BUTTON,
+            };
+            if pressed {
+                rl.is_mouse_button_pressed(mouse)
+            } else {
+                rl.is_mouse_button_down(mouse)
+            }
+        }
     }
-    cube
+}
+
+// held this frame, by any of the action's buttons
+fn held(rl: &RaylibHandle, bindings: &Bindings, action: Action) -> bool {
+    bindings
+        .get(action)
+        .iter()
+        .any(|&b| button_down(rl, b, false))
+}
+
+// went down this frame
+fn pressed(rl: &RaylibHandle, bindings: &Bindings, action: Action) -> bool {
+    bindings
+        .get(action)
+        .iter()
+        .any(|&b| button_down(rl, b, true))
 }
 
 fn main() {
@@ -62,8 +99,101 @@ fn main() {
         .title("software renderer")
         .build();
     rl.set_target_fps(60);
+    // every key and mouse button goes through here, f1 lists them
+    let bindings = match Bindings::load("bindings.txt") {
+        Ok(bindings) => bindings,
+        Err(e) => {
+            println!("no key bindings, using the defaults: {}", e);
+            Bindings::defaults()
+        }
+    };
+    let mut show_bindings = false;
+    // quitting is an action like the rest, so raylib shouldn't grab escape for itself
+    rl.set_exit_key(None);
 
-    let cube = make_cube();
+    let mut cube = Mesh::cube();
+    let face_colors = [
+        mm::Vec3::new(1.0, 0.3, 0.3),
+        mm::Vec3::new(0.3, 1.0, 0.3),
+        mm::Vec3::new(0.3, 0.3, 1.0),
+        mm::Vec3::new(1.0, 1.0, 0.3),
+        mm::Vec3::new(1.0, 0.3, 1.0),
+        mm::Vec3::new(0.3, 1.0, 1.0),
+    ];
+    for (i, color) in cube.colors.iter_mut().enumerate() {
+        *color = face_colors[i / 4];
+    }
+    let mut floor = Mesh::plane(40.0, 10.0);
+    for color in floor.colors.iter_mut() {
+        *color = mm::Vec3::new(0.6, 0.6, 0.6);
+    }
+    let floor_pos = mm::Vec3::new(0.0, -3.0, 0.0);
+    // a ring of pillars standing on the floor, enough objects for frustum culling to matter
+    let mut pillar = Mesh::cube();
+    for color in pillar.colors.iter_mut() {
+        *color = mm::Vec3::new(0.8, 0.75, 0.7);
+    }
+    let pillar_bounds = pillar.bounds();
+    let pillar_scale = mm::Vec3::new(1.0, 4.0, 1.0);
+    let pillars: Vec<mm::Vec3> = (0..16)
+        .map(|i| {
+            let a = i as f32 / 16.0 * std::f32::consts::TAU;
+            mm::Vec3::new(a.cos() * 10.0, floor_pos.y + 2.0, a.sin() * 10.0)
+        })
+        .collect();
+    let floor_bounds = floor.bounds();
+    let cube_texture =
+        Texture::checkerboard(64, 8, mm::Vec3::one(), mm::Vec3::new(0.45, 0.45, 0.5));
+    // l cycles the sun, a spot light and a point light, h cycles the shadow filtering
+    let light_dir = mm::Vec3::new(-0.5, -1.0, 1.0);
+    let spot_pos = mm::Vec3::new(-6.0, 8.0, -4.0);
+    let point_pos = mm::Vec3::new(-3.0, 5.0, -4.0);
+    let light_names = ["sun", "spot", "point"];
+    let mut light_mode = 0;
+    let shadow_filters = [
+        None,
+        Some(ShadowFilter::Hard),
+        Some(ShadowFilter::Pcf(1)),
+        Some(ShadowFilter::Pcss {
+            light_size: 2.0,
+            search_radius: 4,
+            max_radius: 6.0,
+        }),
+    ];
+    let mut shadow_mode = 2;
+    let mut shadow_map = ShadowMap::new(512, ShadowFilter::Pcf(1));
+    let mut cube_shadow_map = CubeShadowMap::new(256, ShadowFilter::Pcf(1));
+
+    // g switches to the deferred path, lit by a swarm of small point lights over the floor
+    let mut deferred = false;
+    let mut gbuffer = GBuffer::new(gameboy_dims.x as usize, gameboy_dims.y as usize);
+    let mut rng = Rng::new(7);
+    let swarm: Vec<(mm::Vec3, mm::Vec3, f32)> = (0..256)
+        .map(|_| {
+            let x = rng.next_f32() * 30.0 - 15.0;
+            let z = rng.next_f32() * 30.0 - 15.0;
+            let color = mm::Vec3::new(rng.next_f32(), rng.next_f32(), rng.next_f32());
+            let phase = rng.next_f32() * std::f32::consts::TAU;
+            (mm::Vec3::new(x, floor_pos.y + 0.5, z), color, phase)
+        })
+        .collect();
+    let mut lights_per_tile = 0.0;
+
+    // b swaps the forward path to pbr shading, lit by an hdr environment if one is around
+    let mut use_pbr = false;
+    let environment = match Environment::load("environment.hdr") {
+        Ok(environment) => environment,
+        Err(e) => {
+            println!("no environment map, using a procedural sky: {}", e);
+            Environment::sky(-light_dir)
+        }
+    };
+    let mut cube_material = PbrMaterial::new(mm::Vec3::one(), 0.0, 0.4);
+    cube_material.base_color_map = Some(cube_texture.clone());
+    // n toggles a normal map on the cube, bevels pulled out of the checkerboard
+    let mut normal_mapping = true;
+    cube_material.normal_map = Some(cube_texture.normal_map_from_height(4.0));
+    let floor_material = PbrMaterial::new(mm::Vec3::one(), 0.0, 0.8);
 
     let mut framebuffer = rl
         .load_render_texture(
@@ -74,187 +204,727 @@ fn main() {
         )
         .unwrap();
 
+    // the software rasterizer draws here, then it gets uploaded to a texture
+    let mut canvas =
+        framebuffer::Framebuffer::new(gameboy_dims.x as usize, gameboy_dims.y as usize);
+    let mut canvas_texture = rl
+        .load_texture_from_image(
+            &thread,
+            &Image::gen_image_color(canvas.width as i32, canvas.height as i32, Color::BLACK),
+        )
+        .unwrap();
+
     //  set the virtual resolution so the moues position is correct
     let mouse_scale = gameboy_dims / dims;
-    rl.set_mouse_scale(mouse_scale.x as f32, mouse_scale.y as f32);
+    rl.set_mouse_scale(mouse_scale.x, mouse_scale.y);
 
-    let cam_speed = 0.1;
     let cube_speed = 0.1;
-    let mut cam_pos = mm::Vec3::new(0.0, 0.0, -10.0);
-    let mut fov = 90.0;
-    let mut near = 0.1;
-    let mut far = 100.0;
+    // k swaps between perspective and orthographic
+    let mut camera = Camera::perspective(
+        90f32.to_radians(),
+        gameboy_dims.x / gameboy_dims.y,
+        0.1,
+        100.0,
+    );
+    camera.ortho_height = 20.0;
+    camera.position = mm::Vec3::new(0.0, 0.0, -10.0);
+    camera.look_at(mm::Vec3::zero(), mm::Vec3::new(0.0, 1.0, 0.0));
+    // wasd to fly, q e down and up, shift to go faster, tab grabs the mouse to look around
+    let mut fly = FlyController::new(&camera);
+    let mut mouse_captured = false;
+    let mut last_mouse = mm::Vec2::new(0.0, 0.0);
+    // j swaps to an orbit camera: left drag turns, middle drag pans, wheel dollies,
+    // y switches turntable and arcball, f frames whatever was last picked
+    let mut orbit_cam = OrbitController::new(&camera, mm::Vec3::zero());
+    let mut orbiting = false;
 
-    let mut mouse_center_normalized = mm::Vec2::new(0.0, 0.0);
-    let mut look_at = mm::Vec3::new(0.0, 0.0, 1.0);
+    // the spinning cube hangs off a scene node at cube_pos, with a small one orbiting it
+    let scene_meshes = [&cube];
+    let scene_spheres: Vec<_> = scene_meshes.iter().map(|m| m.bounding_sphere()).collect();
+    let mut scene = Scene::new();
+    let cube_root = scene.add("cube", Transform::identity(), None);
+    let spinner = scene.add(
+        "spinner",
+        Transform::new(
+            mm::Vec3::zero(),
+            mm::Quat::identity(),
+            mm::Vec3::one() * 4.0,
+        ),
+        Some(cube_root),
+    );
+    scene.attach(spinner, Attachment::Mesh(0));
+    let orbit = scene.add("orbit", Transform::identity(), Some(cube_root));
+    let moon = scene.add(
+        "moon",
+        Transform::from_position(mm::Vec3::new(5.0, 0.0, 0.0)),
+        Some(orbit),
+    );
+    scene.attach(moon, Attachment::Mesh(0));
+    // right click picks, u swaps casting a ray for reading back an id buffer
+    let mut id_buffer = IdBuffer::new(gameboy_dims.x as usize, gameboy_dims.y as usize);
+    let mut pick_buffer = false;
+    let mut picked: Option<Hit> = None;
+    // picking something in the scene graph puts a gizmo on it: left drag a handle to edit,
+    // i cycles move, turn and scale, 8 swaps world and local axes, hold ctrl to snap
+    let mut gizmo = Gizmo::new(GizmoMode::Translate);
+    let mut gizmo_node: Option<NodeId> = None;
+    let mut overlay =
+        framebuffer::Framebuffer::new(gameboy_dims.x as usize, gameboy_dims.y as usize);
+    // 0 draws the debug view: a grid, bounds, normals, axes and the shadow frustum
+    let mut debug_draw = DebugDraw::new();
+    let mut show_debug = false;
 
     let mut cube_pos = mm::Vec3 {
         x: 2.0,
         y: 1.0,
         z: 0.0,
     };
+    let mut outline = false;
+    let mut render_mode = RenderMode::Modern;
+    // m cycles through these
+    let antialias_modes = [
+        Antialias::None,
+        Antialias::Msaa(2),
+        Antialias::Msaa(4),
+        Antialias::Msaa(8),
+        Antialias::Ssaa(4),
+    ];
+    let mut antialias_mode = 0;
+    // p cycles screen space antialiasing: off, fxaa, smaa
+    let mut post_aa = 0;
+    // everything starts off, the number keys toggle the rest of the chain
+    let mut post_chain = PostChain::new();
+    post_chain.push(Box::new(Ssao::default()));
+    post_chain.push(Box::new(Fxaa::new()));
+    post_chain.push(Box::new(Smaa::new()));
+    post_chain.push(Box::new(Sharpen::default()));
+    post_chain.push(Box::new(Blur::default()));
+    post_chain.push(Box::new(ChromaticAberration::default()));
+    post_chain.push(Box::new(Vignette::default()));
+    // grading is optional, drop a grade.cube in the working directory to try it
+    match Lut::load("grade.cube") {
+        Ok(lut) => post_chain.push(Box::new(lut)),
+        Err(e) => println!("no color grading: {}", e),
+    }
+    post_chain.push(Box::new(Gamma::default()));
+    post_chain.push(Box::new(Rgb555::default()));
+    post_chain.push(Box::new(ViFilter::new()));
+
+    // c cycles the output palette, v the dithering, a user palette joins the list if present
+    let mut palettes = vec![
+        Palette::dmg(),
+        Palette::cga(),
+        Palette::ega(),
+        Palette::pico8(),
+    ];
+    for path in ["palette.hex", "palette.gpl"] {
+        if let Ok(palette) = Palette::load(path) {
+            palettes.push(palette);
+        }
+    }
+    let dithers = [
+        Dither::None,
+        Dither::Bayer(BayerSize::Two),
+        Dither::Bayer(BayerSize::Four),
+        Dither::Bayer(BayerSize::Eight),
+        Dither::BlueNoise,
+        Dither::FloydSteinberg,
+        Dither::Atkinson,
+    ];
+    // palette 0 is full color
+    let mut palette_mode = 0;
+    let mut dither_mode = 2;
+    post_chain.push(Box::new(Quantize::new(
+        palettes[0].clone(),
+        dithers[dither_mode],
+    )));
+    for entry in post_chain.entries.iter_mut() {
+        entry.enabled = false;
+    }
+    let post_toggles = [
+        (Action::ToggleGamma, "gamma"),
+        (Action::ToggleLut, "lut"),
+        (Action::ToggleVignette, "vignette"),
+        (Action::ToggleChromaticAberration, "chromatic aberration"),
+        (Action::ToggleSharpen, "sharpen"),
+        (Action::ToggleBlur, "gaussian blur"),
+        (Action::ToggleSsao, "ssao"),
+    ];
 
     while !rl.window_should_close() {
+        // grabbing or letting go can warp the cursor, so that frame doesn't turn
+        let grabbed = pressed(&rl, &bindings, Action::ToggleMouseLook);
+        if grabbed {
+            mouse_captured = !mouse_captured;
+            if mouse_captured {
+                rl.disable_cursor();
+            } else {
+                rl.enable_cursor();
+            }
+        }
+
         let mut dt = rl.begin_drawing(&thread);
+        // filled in by the culling inside the render target block, shown in the hud
+        let (drawn, culled);
         {
             let mut d = dt.begin_texture_mode(&thread, &mut framebuffer);
             d.clear_background(Color::BLACK);
 
-            // draw the mouse
             let mouse_pos = d.get_mouse_position();
-            let mp: mm::Vec2 = mm::Vec2::new(mouse_pos.x, mouse_pos.y);
-            mouse_center_normalized = mp / gameboy_dims - mm::Vec2::new(0.5, 0.5);
 
-            d.draw_circle_v(mouse_pos, 2.0, Color::RED);
-            // quit if escape is pressed
-            if d.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            if pressed(&d, &bindings, Action::Quit) {
                 break;
             }
-            // if a key is pressed, move the camera left
-            if d.is_key_down(KeyboardKey::KEY_A) {
-                cam_pos.x -= cam_speed;
+            if pressed(&d, &bindings, Action::ShowBindings) {
+                show_bindings = !show_bindings;
             }
-            // if d is pressed, move the camera right
-            if d.is_key_down(KeyboardKey::KEY_D) {
-                cam_pos.x += cam_speed;
+            let mouse = mm::Vec2::new(mouse_pos.x, mouse_pos.y);
+            let mut look = mm::Vec2::new(0.0, 0.0);
+            if mouse_captured && !grabbed {
+                look = mouse - last_mouse;
             }
-            // if w is pressed, move the camera up
-            if d.is_key_down(KeyboardKey::KEY_W) {
-                cam_pos.y += cam_speed;
+            last_mouse = mouse;
+            let axis = |pos: bool, neg: bool| pos as i32 as f32 - neg as i32 as f32;
+            let input = ControlInput {
+                look,
+                movement: mm::Vec3::new(
+                    axis(
+                        held(&d, &bindings, Action::MoveRight),
+                        held(&d, &bindings, Action::MoveLeft),
+                    ),
+                    axis(
+                        held(&d, &bindings, Action::MoveUp),
+                        held(&d, &bindings, Action::MoveDown),
+                    ),
+                    axis(
+                        held(&d, &bindings, Action::MoveForward),
+                        held(&d, &bindings, Action::MoveBack),
+                    ),
+                ),
+                sprint: held(&d, &bindings, Action::Sprint),
+            };
+            if pressed(&d, &bindings, Action::ToggleOrbit) {
+                orbiting = !orbiting;
+                // each one picks up from wherever the other left the camera
+                if orbiting {
+                    let mode = orbit_cam.mode;
+                    orbit_cam = OrbitController::new(&camera, orbit_cam.target);
+                    orbit_cam.mode = mode;
+                } else {
+                    fly = FlyController::new(&camera);
+                }
             }
-            // if s is pressed, move the camera down
-            if d.is_key_down(KeyboardKey::KEY_S) {
-                cam_pos.y -= cam_speed;
+            // with the mouse grabbed the cursor is the middle of the screen
+            let cursor = if mouse_captured {
+                gameboy_dims * 0.5
+            } else {
+                mouse
+            };
+            if !gizmo.dragging() {
+                if pressed(&d, &bindings, Action::CycleGizmoMode) {
+                    gizmo.mode = match gizmo.mode {
+                        GizmoMode::Translate => GizmoMode::Rotate,
+                        GizmoMode::Rotate => GizmoMode::Scale,
+                        GizmoMode::Scale => GizmoMode::Translate,
+                    };
+                }
+                if pressed(&d, &bindings, Action::ToggleGizmoSpace) {
+                    gizmo.space = match gizmo.space {
+                        GizmoSpace::World => GizmoSpace::Local,
+                        GizmoSpace::Local => GizmoSpace::World,
+                    };
+                }
             }
-
-            // if q is pressed, move the camera forward
-            if d.is_key_down(KeyboardKey::KEY_Q) {
-                cam_pos.z += cam_speed;
+            gizmo.snap = held(&d, &bindings, Action::GizmoSnap);
+            if let Some(node) = gizmo_node {
+                let ray = camera.screen_to_ray(cursor, gameboy_dims.x, gameboy_dims.y);
+                if !held(&d, &bindings, Action::GizmoDrag) {
+                    gizmo.end();
+                }
+                if gizmo.dragging() {
+                    gizmo.drag(&mut scene, &ray);
+                    if node == cube_root {
+                        cube_pos = scene.node(cube_root).transform().position;
+                    }
+                } else {
+                    gizmo.hover(&scene, node, &camera, &ray);
+                    if pressed(&d, &bindings, Action::GizmoDrag) {
+                        gizmo.begin(&scene, node, &camera, &ray);
+                    }
+                }
             }
-            // if e is pressed, move the camera backward
-            if d.is_key_down(KeyboardKey::KEY_E) {
-                cam_pos.z -= cam_speed;
+            // a drag that starts on a handle belongs to the gizmo, not the orbit camera
+            let gizmo_busy = gizmo_node.is_some() && gizmo.active().is_some();
+            let frame_selected = orbiting && pressed(&d, &bindings, Action::FrameSelected);
+            if pressed(&d, &bindings, Action::TogglePickBuffer) {
+                pick_buffer = !pick_buffer;
+            }
+            if orbiting {
+                if pressed(&d, &bindings, Action::CycleOrbitStyle) {
+                    orbit_cam.mode = match orbit_cam.mode {
+                        OrbitMode::Turntable => OrbitMode::Arcball,
+                        OrbitMode::Arcball => OrbitMode::Turntable,
+                    };
+                }
+                let input = OrbitInput {
+                    cursor: mouse,
+                    screen: gameboy_dims,
+                    rotate: held(&d, &bindings, Action::OrbitRotate) && !gizmo_busy,
+                    pan: held(&d, &bindings, Action::OrbitPan),
+                    scroll: d.get_mouse_wheel_move(),
+                };
+                orbit_cam.update(&mut camera, &input);
+            } else {
+                fly.update(&mut camera, &input, d.get_frame_time());
             }
 
             // if z is pressed, zoom in
-            if d.is_key_down(KeyboardKey::KEY_Z) {
-                fov -= 1.0;
-                if fov < 45.0 {
-                    fov = 45.0;
-                }
+            if held(&d, &bindings, Action::ZoomIn) {
+                camera.fov = (camera.fov - 1f32.to_radians()).max(45f32.to_radians());
+                camera.ortho_height = (camera.ortho_height - 0.2).max(5.0);
             }
             // if x is pressed, zoom out
-            if d.is_key_down(KeyboardKey::KEY_X) {
-                fov += 1.0;
-                if fov > 120.0 {
-                    fov = 120.0;
-                }
+            if held(&d, &bindings, Action::ZoomOut) {
+                camera.fov = (camera.fov + 1f32.to_radians()).min(120f32.to_radians());
+                camera.ortho_height = (camera.ortho_height + 0.2).min(60.0);
             }
-            // r to move cam forward
-            if d.is_key_down(KeyboardKey::KEY_R) {
-                cam_pos.z += cam_speed;
+            if pressed(&d, &bindings, Action::ToggleOrthographic) {
+                camera.orthographic = !camera.orthographic;
             }
-            // f to move cam backward
-            if d.is_key_down(KeyboardKey::KEY_F) {
-                cam_pos.z -= cam_speed;
-            }
-
             //arrow keys to move the cube
-            if d.is_key_down(KeyboardKey::KEY_LEFT) {
+            if held(&d, &bindings, Action::MoveCubeLeft) {
                 cube_pos.x -= cube_speed;
             }
-            if d.is_key_down(KeyboardKey::KEY_RIGHT) {
+            if held(&d, &bindings, Action::MoveCubeRight) {
                 cube_pos.x += cube_speed;
             }
-            if d.is_key_down(KeyboardKey::KEY_UP) {
+            if held(&d, &bindings, Action::MoveCubeUp) {
                 cube_pos.y += cube_speed;
             }
-            if d.is_key_down(KeyboardKey::KEY_DOWN) {
+            if held(&d, &bindings, Action::MoveCubeDown) {
                 cube_pos.y -= cube_speed;
             }
-
-            let proj = mm::Mat4::perspective(
-                fov * std::f32::consts::PI / 180.0,
-                gameboy_dims.x / gameboy_dims.y,
-                near,
-                far,
-            );
-
-            let cube_scale = mm::Vec3::one() * 10.0;
+            // o toggles the stencil outline around the cube
+            if pressed(&d, &bindings, Action::ToggleOutline) {
+                outline = !outline;
+            }
+            // m cycles the antialiasing mode
+            if pressed(&d, &bindings, Action::CycleAntialias) {
+                antialias_mode = (antialias_mode + 1) % antialias_modes.len();
+                canvas.set_antialias(antialias_modes[antialias_mode]);
+            }
+            // p cycles the post process antialiasing
+            if pressed(&d, &bindings, Action::CyclePostAntialias) {
+                post_aa = (post_aa + 1) % 3;
+                post_chain.set_enabled("fxaa", post_aa == 1);
+                post_chain.set_enabled("smaa", post_aa == 2);
+            }
+            // t cycles the render mode, modern, ps1 then n64
+            if pressed(&d, &bindings, Action::CycleRenderMode) {
+                render_mode = match render_mode {
+                    RenderMode::Modern => RenderMode::Ps1,
+                    RenderMode::Ps1 => RenderMode::N64,
+                    RenderMode::N64 => RenderMode::Modern,
+                };
+                post_chain.set_enabled("rgb555", render_mode == RenderMode::Ps1);
+                post_chain.set_enabled("vi", render_mode == RenderMode::N64);
+            }
+            // c and v pick the palette and dithering
+            let palette_pressed = pressed(&d, &bindings, Action::CyclePalette);
+            let dither_pressed = pressed(&d, &bindings, Action::CycleDither);
+            if palette_pressed {
+                palette_mode = (palette_mode + 1) % (palettes.len() + 1);
+            }
+            if dither_pressed {
+                dither_mode = (dither_mode + 1) % dithers.len();
+            }
+            if palette_pressed || dither_pressed {
+                if palette_mode > 0 {
+                    post_chain.replace(
+                        "palette",
+                        Box::new(Quantize::new(
+                            palettes[palette_mode - 1].clone(),
+                            dithers[dither_mode],
+                        )),
+                    );
+                }
+                post_chain.set_enabled("palette", palette_mode > 0);
+            }
+            // l swaps the light, h the shadow filter
+            if pressed(&d, &bindings, Action::CycleLight) {
+                light_mode = (light_mode + 1) % light_names.len();
+            }
+            // g swaps forward and deferred shading
+            if pressed(&d, &bindings, Action::ToggleDeferred) {
+                deferred = !deferred;
+            }
+            if pressed(&d, &bindings, Action::CycleShadows) {
+                shadow_mode = (shadow_mode + 1) % shadow_filters.len();
+            }
+            // b swaps lambert and pbr in the forward path
+            if pressed(&d, &bindings, Action::TogglePbr) {
+                use_pbr = !use_pbr;
+            }
+            if pressed(&d, &bindings, Action::ToggleNormalMap) {
+                normal_mapping = !normal_mapping;
+            }
+            if pressed(&d, &bindings, Action::ToggleDebugDraw) {
+                show_debug = !show_debug;
+            }
+            // number keys toggle the other post effects
+            for (action, name) in post_toggles {
+                if pressed(&d, &bindings, action) {
+                    post_chain.toggle(name);
+                }
+            }
 
             // let time = d.get_time() as f32;
             // let rotation_speed = 10.0;
             // let rotation = mm::Vec3::one() * time * rotation_speed;
 
-            let up_axis = mm::Vec3::new(0.0, 1.0, 0.0);
-
-            let view_from_center = mm::Vec3::zero();
-            let cam_dir = mm::Vec3::new(0.0, 0.0, 1.0);
-            look_at = cam_pos + cam_dir;
-            let view = mm::Mat4::look_at(view_from_center, cam_dir, up_axis);
-
-            // get cube cam space
-            let model = mm::Mat4::identity();
+            // animate the scene, only the touched nodes and whatever hangs under them get rebuilt
+            let time = d.get_time() as f32;
+            scene.set_position(cube_root, cube_pos);
             let spin_axis = mm::Vec3::new(1.0, 1.0, 1.0);
-            let model = model * mm::Mat4::translation(cube_pos - cam_pos);
-            let model = model * mm::Mat4::rotation(d.get_time() as f32 * 3.0, spin_axis);
-            let model = model * mm::Mat4::scale(cube_scale);
+            scene.set_rotation(spinner, mm::Quat::from_axis_angle(spin_axis, time * 3.0));
+            let up = mm::Vec3::new(0.0, 1.0, 0.0);
+            scene.set_rotation(orbit, mm::Quat::from_axis_angle(up, time));
+            let tumble = mm::Vec3::new(1.0, 0.0, 0.0);
+            scene.set_rotation(moon, mm::Quat::from_axis_angle(tumble, time * 5.0));
+            scene.update();
+            let scene_models = scene.meshes();
+
+            let floor_model = mm::Mat4::translation(floor_pos);
+            let pillar_models: Vec<mm::Mat4> = pillars
+                .iter()
+                .map(|p| mm::Mat4::translation(*p) * mm::Mat4::scale(pillar_scale))
+                .collect();
 
-            // let cube_transform = proj * view * model;
-            let cube_transform = view * proj * model;
-            // let cube_transform = model * view * proj;
+            let base_state = match render_mode {
+                RenderMode::Modern => state::RenderState::default(),
+                RenderMode::Ps1 => state::RenderState::ps1(),
+                RenderMode::N64 => state::RenderState::n64(),
+            };
+            // the cube marks its pixels with 1 in the stencil buffer
+            let mut cube_state = base_state;
+            if outline {
+                cube_state.stencil = state::StencilState::write(1);
+            }
 
-            // make new transformed cube vertices
-            let mut cube_vertices: Vec<mm::Vec3> = Vec::new();
-            for i in 0..cube.len() {
-                cube_vertices.push(cube[i] * cube_transform);
+            // everything the mouse can pick, hits point back into this list
+            let mut pickables: Vec<(&Mesh, mm::Mat4)> = vec![(&floor, floor_model)];
+            pickables.extend(pillar_models.iter().map(|m| (&pillar, *m)));
+            pickables.extend(
+                scene_models
+                    .iter()
+                    .map(|(m, model)| (scene_meshes[*m], *model)),
+            );
+            // scene meshes come after the floor and pillars
+            let first = 1 + pillars.len();
+            if pressed(&d, &bindings, Action::Pick) {
+                let ray = camera.screen_to_ray(cursor, gameboy_dims.x, gameboy_dims.y);
+                picked = if pick_buffer {
+                    let view_proj = camera.view_proj();
+                    id_buffer.clear();
+                    for (i, (mesh, model)) in pickables.iter().enumerate() {
+                        // the same state each was drawn with, so snapping and culling match
+                        let state = if i < first { &base_state } else { &cube_state };
+                        id_buffer.draw(state, mesh, model, &view_proj, i);
+                    }
+                    if cursor.x < 0.0 || cursor.y < 0.0 {
+                        None
+                    } else {
+                        id_buffer.pick(cursor.x as usize, cursor.y as usize, &ray)
+                    }
+                } else {
+                    pick::pick(&ray, &pickables)
+                };
+                // the gizmo goes on the top of the picked mesh's hierarchy
+                // so whatever animates underneath keeps going
+                let mesh_nodes = scene.mesh_nodes();
+                gizmo_node = picked.and_then(|hit| {
+                    let mut node = *mesh_nodes.get(hit.object.checked_sub(first)?)?;
+                    while let Some(parent) = scene.node(node).parent() {
+                        node = parent;
+                    }
+                    Some(node)
+                });
+                gizmo.end();
+                gizmo.hovered = None;
             }
+            if frame_selected {
+                if let Some(hit) = picked {
+                    let (mesh, model) = pickables[hit.object];
+                    orbit_cam.frame(&mut camera, &mesh.bounding_sphere().transform(&model));
+                }
+            }
+
+            let eye = camera.position;
+            let view_proj = camera.view_proj();
+
+            // skip whole objects outside the view before touching their vertices
+            // shadow maps still get everything, casters can be off screen
+            let mut culler = Culler::new(&view_proj);
+            let floor_visible = culler.aabb(&floor_bounds, &floor_model);
+            let visible_scene: Vec<(usize, mm::Mat4)> = scene_models
+                .iter()
+                .filter(|(mesh, model)| culler.sphere(&scene_spheres[*mesh], model))
+                .copied()
+                .collect();
+            let visible_pillars: Vec<mm::Mat4> = pillar_models
+                .iter()
+                .filter(|model| culler.aabb(&pillar_bounds, model))
+                .copied()
+                .collect();
+            (drawn, culled) = (culler.drawn, culler.culled);
 
-            //get all possible pairs of edges
-            let mut edges: Vec<(usize, usize)> = Vec::new();
-            for i in 0..cube_vertices.len() {
-                for j in i + 1..cube_vertices.len() {
-                    edges.push((i, j));
+            let light = match light_mode {
+                0 => Light::directional(light_dir, mm::Vec3::one(), 0.8),
+                1 => Light::spot(
+                    spot_pos,
+                    cube_pos - spot_pos,
+                    std::f32::consts::PI / 3.0,
+                    40.0,
+                    mm::Vec3::one(),
+                    120.0,
+                ),
+                _ => Light::point(point_pos, 40.0, mm::Vec3::one(), 60.0),
+            };
+            let point_light = matches!(light.kind, LightKind::Point { .. });
+
+            // render the shadow casters from the light first
+            let shadows = !deferred
+                && match shadow_filters[shadow_mode] {
+                    Some(filter) if point_light => {
+                        cube_shadow_map.filter = filter;
+                        cube_shadow_map.setup(&light).is_ok()
+                    }
+                    Some(filter) => {
+                        shadow_map.filter = filter;
+                        shadow_map.setup(&light, cube_pos, 16.0).is_ok()
+                    }
+                    None => false,
+                };
+            if shadows {
+                let floor_world = floor.world_positions(&floor_model);
+                if point_light {
+                    cube_shadow_map.clear();
+                    for (mesh, model) in &scene_models {
+                        let mesh = scene_meshes[*mesh];
+                        cube_shadow_map.draw(&mesh.world_positions(model), &mesh.indices);
+                    }
+                    cube_shadow_map.draw(&floor_world, &floor.indices);
+                    for model in &pillar_models {
+                        cube_shadow_map.draw(&pillar.world_positions(model), &pillar.indices);
+                    }
+                } else {
+                    shadow_map.clear();
+                    for (mesh, model) in &scene_models {
+                        let mesh = scene_meshes[*mesh];
+                        shadow_map.draw(&mesh.world_positions(model), &mesh.indices);
+                    }
+                    shadow_map.draw(&floor_world, &floor.indices);
+                    for model in &pillar_models {
+                        shadow_map.draw(&pillar.world_positions(model), &pillar.indices);
+                    }
                 }
             }
 
-            let half_screen = gameboy_dims / 2.0;
+            canvas.clear(mm::Vec3::zero());
 
-            // draw the edges
-            for edge in edges {
-                let v1 = cube_vertices[edge.0];
-                let v2 = cube_vertices[edge.1];
-                let rlv2_1 = raylib::ffi::Vector2 {
-                    x: v1.x + half_screen.x,
-                    y: v1.y + half_screen.y,
+            let filter = match render_mode {
+                RenderMode::Modern => Filter::Bilinear,
+                RenderMode::Ps1 => Filter::Nearest,
+                RenderMode::N64 => Filter::ThreePoint,
+            };
+            if deferred {
+                let time = d.get_time() as f32;
+                let lights: Vec<Light> = swarm
+                    .iter()
+                    .map(|&(position, color, phase)| {
+                        let bob = mm::Vec3::new(0.0, (time * 2.0 + phase).sin() * 0.4 + 0.4, 0.0);
+                        Light::point(position + bob, 3.0, color, 6.0)
+                    })
+                    .collect();
+                let mut surface = |frag: &raster::Fragment| {
+                    Some(Surface {
+                        albedo: cube_texture.sample(frag.varying.uv, filter) * frag.varying.color,
+                        normal: frag.varying.normal,
+                        material: Material {
+                            specular: 0.5,
+                            shininess: 32.0,
+                        },
+                    })
                 };
-                let rlv2_2 = raylib::ffi::Vector2 {
-                    x: v2.x + half_screen.x,
-                    y: v2.y + half_screen.y,
+                gbuffer.clear();
+                if floor_visible {
+                    gbuffer.draw(
+                        &base_state,
+                        &floor.vertices(&floor_model, &view_proj),
+                        &floor.indices,
+                        &mut surface,
+                    );
+                }
+                for (mesh, model) in &visible_scene {
+                    let mesh = scene_meshes[*mesh];
+                    gbuffer.draw(
+                        &cube_state,
+                        &mesh.vertices(model, &view_proj),
+                        &mesh.indices,
+                        &mut surface,
+                    );
+                }
+                for model in &visible_pillars {
+                    gbuffer.draw(
+                        &base_state,
+                        &pillar.vertices(model, &view_proj),
+                        &pillar.indices,
+                        &mut surface,
+                    );
+                }
+                let tiles = LightTiles::build(&gbuffer, &lights, &view_proj, 16);
+                lights_per_tile = tiles.average();
+                gbuffer.shade(&mut canvas, &lights, &tiles, eye, mm::Vec3::one() * 0.05);
+            } else {
+                let shade = |frag: &raster::Fragment, material: &PbrMaterial| {
+                    let (l, radiance) = light.incident(frag.varying.world);
+                    let diffuse = frag.varying.normal.dot(&l).max(0.0);
+                    let visibility = if !shadows || diffuse <= 0.0 {
+                        1.0
+                    } else if point_light {
+                        cube_shadow_map.visibility(frag.varying.world)
+                    } else {
+                        shadow_map.visibility(frag.varying.world)
+                    };
+                    // a zero tangent turns the normal map off
+                    let tangent = if normal_mapping {
+                        frag.varying.tangent
+                    } else {
+                        mm::Vec4::zero()
+                    };
+                    let mut s =
+                        material.sample(frag.varying.uv, filter, frag.varying.normal, tangent);
+                    if !use_pbr {
+                        let albedo =
+                            cube_texture.sample(frag.varying.uv, filter) * frag.varying.color;
+                        let bumped = s.normal.dot(&l).max(0.0);
+                        return Some(albedo * (0.2 + radiance * (bumped * visibility)));
+                    }
+                    s.base_color *= frag.varying.color;
+                    // lights were tuned for lambert without the 1/pi
+                    let v = (eye - frag.varying.world).normalize();
+                    let direct = pbr::direct(&s, v, l, radiance * std::f32::consts::PI);
+                    Some(direct * visibility + pbr::ambient(&s, v, &environment) + s.emissive)
                 };
-                d.draw_line_ex(rlv2_1, rlv2_2, 1.0, Color::WHITE);
+                if floor_visible {
+                    draw_mesh(
+                        &mut canvas,
+                        &base_state,
+                        render_mode,
+                        &floor.vertices(&floor_model, &view_proj),
+                        &floor.indices,
+                        &mut |frag: &raster::Fragment| shade(frag, &floor_material),
+                    );
+                }
+                for (mesh, model) in &visible_scene {
+                    let mesh = scene_meshes[*mesh];
+                    draw_mesh(
+                        &mut canvas,
+                        &cube_state,
+                        render_mode,
+                        &mesh.vertices(model, &view_proj),
+                        &mesh.indices,
+                        &mut |frag: &raster::Fragment| shade(frag, &cube_material),
+                    );
+                }
+                for model in &visible_pillars {
+                    draw_mesh(
+                        &mut canvas,
+                        &base_state,
+                        render_mode,
+                        &pillar.vertices(model, &view_proj),
+                        &pillar.indices,
+                        &mut |frag: &raster::Fragment| shade(frag, &floor_material),
+                    );
+                }
             }
 
-            // // draw the cube, one dot at a time
-            for vert in &cube_vertices {
-                let rlffiv2p = raylib::ffi::Vector2 {
-                    x: vert.x + dims.x / 2.0,
-                    y: vert.y + dims.y / 2.0,
+            // slightly bigger cubes drawn only where the scene's cubes are not
+            if outline && !deferred {
+                let outline_state = state::RenderState {
+                    depth_test: false,
+                    stencil: state::StencilState::mask(state::Compare::NotEqual, 1),
+                    ..state::RenderState::default()
                 };
-                d.draw_circle_v(rlffiv2p, 2.0, Color::BLUE);
-            }
-
-            // // draw the cube, one line at a time
-            // for i in 0..cube_vertices.len() {
-            //     let vert1 = cube_vertices[i];
-            //     let vert2 = cube_vertices[(i + 1) % cube_vertices.len()];
-            //     let rlffiv2p1 =
-            //     let rlffiv2p2 = raylib::ffi::Vector2 {
-            //         x: vert2.x + gameboy_dims.x / 2.0,
-            //         y: vert2.y + gameboy_dims.y / 2.0,
-            //     };
-            //     d.draw_line_v(rlffiv2p1, rlffiv2p2, Color::RED);
-            // }
+                for (mesh, model) in &visible_scene {
+                    let mesh = scene_meshes[*mesh];
+                    let grown = *model * mm::Mat4::scale(mm::Vec3::one() * 1.1);
+                    raster::draw_indexed(
+                        &mut canvas,
+                        &outline_state,
+                        &mesh.vertices(&grown, &view_proj),
+                        &mesh.indices,
+                        &mut |_: &raster::Fragment| Some(mm::Vec3::new(1.0, 1.0, 1.0)),
+                    );
+                }
+            }
+
+            // the lighting pass writes resolved color straight away
+            if !deferred {
+                canvas.resolve();
+            }
+            post_chain.set_view(&view_proj);
+            post_chain.run(&mut canvas);
+            // debug shapes go over the post processed frame, hidden by the scene's depth
+            if show_debug {
+                debug_draw.clear();
+                debug_draw.grid(eye, floor_pos.y, 1.0, camera.far, mm::Vec3::one() * 0.3);
+                for (index, model) in &visible_scene {
+                    let mesh = scene_meshes[*index];
+                    debug_draw.oriented_box(&mesh.bounds(), model, mm::Vec3::new(1.0, 1.0, 0.0));
+                    debug_draw.aabb(
+                        &mesh.bounds().transform(model),
+                        mm::Vec3::new(0.0, 1.0, 1.0),
+                    );
+                    debug_draw.sphere(
+                        &scene_spheres[*index].transform(model),
+                        mm::Vec3::new(1.0, 0.0, 1.0),
+                    );
+                    debug_draw.normals(mesh, model, 0.5, mm::Vec3::new(0.5, 0.5, 1.0));
+                }
+                for model in &visible_pillars {
+                    debug_draw.oriented_box(&pillar_bounds, model, mm::Vec3::new(1.0, 1.0, 0.0));
+                }
+                if shadows && !point_light {
+                    debug_draw.frustum(&shadow_map.view_proj, mm::Vec3::new(1.0, 0.5, 0.0));
+                }
+                if let Some(hit) = picked {
+                    debug_draw.point(hit.point, 3.0, mm::Vec3::new(1.0, 0.0, 1.0));
+                }
+                // axes show through everything
+                debug_draw.depth_test = false;
+                debug_draw.axes(&mm::Mat4::identity(), 2.0);
+                for node in scene.mesh_nodes() {
+                    debug_draw.axes(&scene.world(node), 1.0);
+                }
+                debug_draw.depth_test = true;
+                overlay.clear(mm::Vec3::zero());
+                overlay.copy_depth(&canvas);
+                debug_draw.draw(&mut overlay, &view_proj);
+                canvas.overlay(&overlay);
+            }
+            if let Some(node) = gizmo_node {
+                overlay.clear(mm::Vec3::zero());
+                gizmo.draw(&mut overlay, &scene, node, &camera);
+                canvas.overlay(&overlay);
+            }
+            canvas_texture.update_texture(&canvas.to_rgba8());
+            d.draw_texture(&canvas_texture, 0, 0, Color::WHITE);
+
+            // draw the mouse, unless it's been grabbed for looking around
+            if !mouse_captured {
+                d.draw_circle_v(mouse_pos, 2.0, Color::RED);
+            }
         }
         // draw render target to screen
         // render target is 160x144
@@ -278,20 +948,44 @@ fn main() {
             Color::WHITE,
         );
 
+        // frame rate in the top right, to see what the post effects cost
+        dt.draw_fps(dims.x as i32 - 100, 0);
         // draw the cam coords in the top left
-        dt.draw_text(&format!("cam pos: {:?}", cam_pos), 0, 0, 20, Color::WHITE);
+        dt.draw_text(
+            &format!("cam pos: {:?}", camera.position),
+            0,
+            0,
+            20,
+            Color::WHITE,
+        );
         // draw the look at point coords in the top left under cam
-        dt.draw_text(&format!("look at: {:?}", look_at), 0, 20, 20, Color::WHITE);
-        // draw the mouse normalized coords in the top left under look at
         dt.draw_text(
-            &format!("mouse: {:?}", mouse_center_normalized),
+            &format!("look at: {:?}", camera.position + camera.forward()),
             0,
-            40,
+            20,
             20,
             Color::WHITE,
         );
+        // draw the camera controller state in the top left under look at
+        let control = if orbiting {
+            format!("orbit {:?}: {:.1} away", orbit_cam.mode, orbit_cam.distance)
+        } else {
+            let grab = if mouse_captured { "" } else { " (tab to look)" };
+            format!(
+                "yaw: {:.0} pitch: {:.0}{}",
+                fly.yaw.to_degrees(),
+                fly.pitch.to_degrees(),
+                grab
+            )
+        };
+        dt.draw_text(&control, 0, 40, 20, Color::WHITE);
         // draw field of view in the top left under mouse
-        dt.draw_text(&format!("fov: {}", fov), 0, 60, 20, Color::WHITE);
+        let lens = if camera.orthographic {
+            format!("ortho: {:.1}", camera.ortho_height)
+        } else {
+            format!("fov: {:.0}", camera.fov.to_degrees())
+        };
+        dt.draw_text(&lens, 0, 60, 20, Color::WHITE);
         // draw cube pos
         dt.draw_text(
             &format!("cube pos: {:?}", cube_pos),
@@ -300,5 +994,118 @@ fn main() {
             20,
             Color::WHITE,
         );
+        // draw the antialiasing mode under cube pos
+        dt.draw_text(
+            &format!("aa: {:?}", canvas.antialias),
+            0,
+            100,
+            20,
+            Color::WHITE,
+        );
+        // draw the post chain under the antialiasing mode
+        dt.draw_text(
+            &format!("post: {:?}", post_chain.enabled_names()),
+            0,
+            120,
+            20,
+            Color::WHITE,
+        );
+        // draw the render mode and palette under the post chain
+        let palette_name = match palette_mode {
+            0 => "full color",
+            n => &palettes[n - 1].name,
+        };
+        dt.draw_text(
+            &format!(
+                "mode: {:?} palette: {} {:?}",
+                render_mode, palette_name, dithers[dither_mode]
+            ),
+            0,
+            140,
+            20,
+            Color::WHITE,
+        );
+        // draw the light and shadow filter under the render mode
+        dt.draw_text(
+            &format!(
+                "light: {} shadows: {:?} {}{}",
+                light_names[light_mode],
+                shadow_filters[shadow_mode],
+                if use_pbr { "pbr" } else { "lambert" },
+                if normal_mapping { " bumped" } else { "" }
+            ),
+            0,
+            160,
+            20,
+            Color::WHITE,
+        );
+        // draw the frustum culling counts under the light
+        dt.draw_text(
+            &format!("objects: {} drawn, {} culled", drawn, culled),
+            0,
+            180,
+            20,
+            Color::WHITE,
+        );
+        // draw what was picked under the culling counts
+        let method = if pick_buffer { "id buffer" } else { "ray" };
+        let pick_text = match picked {
+            Some(hit) => {
+                let name = match hit.object {
+                    0 => "floor",
+                    i if i <= pillars.len() => "pillar",
+                    _ => "cube",
+                };
+                format!(
+                    "picked {} {}: triangle {} at {:.1} ({})",
+                    name, hit.object, hit.triangle, hit.distance, method
+                )
+            }
+            None => format!("nothing picked ({})", method),
+        };
+        dt.draw_text(&pick_text, 0, 220, 20, Color::WHITE);
+        // draw the gizmo settings under the pick
+        if let Some(node) = gizmo_node {
+            dt.draw_text(
+                &format!(
+                    "gizmo on {}: {:?} {:?}{}",
+                    scene.node(node).name,
+                    gizmo.mode,
+                    gizmo.space,
+                    if gizmo.snap { " snapping" } else { "" }
+                ),
+                0,
+                240,
+                20,
+                Color::WHITE,
+            );
+        }
+        // draw the deferred light count under the culling counts
+        if deferred {
+            dt.draw_text(
+                &format!(
+                    "deferred: {} lights, {:.1} per tile",
+                    swarm.len(),
+                    lights_per_tile
+                ),
+                0,
+                200,
+                20,
+                Color::WHITE,
+            );
+        }
+        // the bindings list goes over everything in two columns
+        if show_bindings {
+            let width = dt.get_screen_width();
+            let height = dt.get_screen_height();
+            dt.draw_rectangle(0, 0, width, height, Color::new(0, 0, 0, 200));
+            let lines = bindings.lines();
+            let rows = lines.len().div_ceil(2);
+            for (i, line) in lines.iter().enumerate() {
+                let x = (i / rows) as i32 * width / 2 + 10;
+                let y = (i % rows) as i32 * 14 + 10;
+                dt.draw_text(line, x, y, 10, Color::WHITE);
+            }
+        }
     }
 }
@@ -0,0 +1,244 @@
+/*
+    meshes
+    indexed triangle lists with per vertex attributes, plus the vertex stage
+    that takes them into clip space for the rasterizer
+*/
+
+use crate::mm::{Aabb, Mat3, Mat4, Sphere, Vec2, Vec3, Vec4};
+use crate::raster::{Varying, Vertex};
+
+#[derive(Debug, Clone)]
+pub struct Mesh {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<Vec2>,
+    pub colors: Vec<Vec3>,
+    // xyz along increasing u, w is the handedness so bitangent = cross(normal, tangent) * w
+    pub tangents: Vec<Vec4>,
+    // counter clockwise when seen from the front
+    pub indices: Vec<[usize; 3]>,
+}
+
+impl Mesh {
+    pub fn new() -> Mesh {
+        Mesh {
+            positions: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            colors: Vec::new(),
+            tangents: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    // four corners a b c d counter clockwise, split into a b c and a c d
+    fn push_quad(&mut self, corners: [Vec3; 4], normal: Vec3, uv_scale: f32) {
+        let uvs = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(0.0, 1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 0.0),
+        ];
+        let base = self.positions.len();
+        for (corner, uv) in corners.iter().zip(uvs.iter()) {
+            self.positions.push(*corner);
+            self.normals.push(normal);
+            self.uvs.push(*uv * uv_scale);
+            self.colors.push(Vec3::one());
+        }
+        self.indices.push([base, base + 1, base + 2]);
+        self.indices.push([base, base + 2, base + 3]);
+    }
+
+    // unit cube centered on the origin, four vertices per face so normals stay flat
+    // faces are ordered +z -z +x -x +y -y
+    pub fn cube() -> Mesh {
+        let c = [
+            Vec3::new(-0.5, 0.5, 0.5),
+            Vec3::new(0.5, 0.5, 0.5),
+            Vec3::new(0.5, -0.5, 0.5),
+            Vec3::new(-0.5, -0.5, 0.5),
+            Vec3::new(-0.5, 0.5, -0.5),
+            Vec3::new(0.5, 0.5, -0.5),
+            Vec3::new(0.5, -0.5, -0.5),
+            Vec3::new(-0.5, -0.5, -0.5),
+        ];
+        let faces = [
+            ([0, 3, 2, 1], Vec3::new(0.0, 0.0, 1.0)),
+            ([4, 5, 6, 7], Vec3::new(0.0, 0.0, -1.0)),
+            ([1, 2, 6, 5], Vec3::new(1.0, 0.0, 0.0)),
+            ([0, 4, 7, 3], Vec3::new(-1.0, 0.0, 0.0)),
+            ([0, 1, 5, 4], Vec3::new(0.0, 1.0, 0.0)),
+            ([3, 7, 6, 2], Vec3::new(0.0, -1.0, 0.0)),
+        ];
+        let mut mesh = Mesh::new();
+        for (f, normal) in faces {
+            mesh.push_quad([c[f[0]], c[f[1]], c[f[2]], c[f[3]]], normal, 1.0);
+        }
+        mesh.generate_tangents();
+        mesh
+    }
+
+    // flat square on the xz plane facing +y, the texture repeats `tiles` times
+    pub fn plane(size: f32, tiles: f32) -> Mesh {
+        let h = size * 0.5;
+        let mut mesh = Mesh::new();
+        mesh.push_quad(
+            [
+                Vec3::new(-h, 0.0, h),
+                Vec3::new(h, 0.0, h),
+                Vec3::new(h, 0.0, -h),
+                Vec3::new(-h, 0.0, -h),
+            ],
+            Vec3::new(0.0, 1.0, 0.0),
+            tiles,
+        );
+        mesh.generate_tangents();
+        mesh
+    }
+
+    // per vertex tangent frames from the uv layout, following mikktspace's main steps:
+    // each triangle's tangent is projected into the plane of the corner's vertex normal,
+    // weighted by the corner angle and summed, and a vertex where mirrored and unmirrored
+    // triangles meet is split in two so each side keeps its own handedness.
+    // it doesn't do mikktspace's grouping by shared edges or its degenerate triangle
+    // fixups, so maps baked with mikktspace match on plain and mirrored layouts but can
+    // drift slightly where a vertex's triangles disagree a lot
+    pub fn generate_tangents(&mut self) {
+        // what every triangle corner contributes to its vertex
+        struct Corner {
+            triangle: usize,
+            corner: usize,
+            tangent: Vec3,
+            mirrored: bool,
+        }
+        let project = |v: Vec3, normal: Vec3| v - normal * normal.dot(&v);
+        let mut corners: Vec<Vec<Corner>> = (0..self.positions.len()).map(|_| Vec::new()).collect();
+        for (triangle, tri) in self.indices.iter().enumerate() {
+            let p = [
+                self.positions[tri[0]],
+                self.positions[tri[1]],
+                self.positions[tri[2]],
+            ];
+            let uv = [self.uvs[tri[0]], self.uvs[tri[1]], self.uvs[tri[2]]];
+            let (e1, e2) = (p[1] - p[0], p[2] - p[0]);
+            let (d1, d2) = (uv[1] - uv[0], uv[2] - uv[0]);
+            let det = d1.x * d2.y - d2.x * d1.y;
+            if det.abs() < 1e-12 {
+                continue;
+            }
+            // solve e = t * du + b * dv for both edges
+            let t = (e1 * d2.y - e2 * d1.y) / det;
+            let b = (e2 * d1.x - e1 * d2.x) / det;
+            for corner in 0..3 {
+                let normal = self.normals[tri[corner]];
+                let tangent = project(t, normal);
+                if tangent.dot(&tangent) < 1e-12 {
+                    continue;
+                }
+                let a = p[(corner + 1) % 3] - p[corner];
+                let c = p[(corner + 2) % 3] - p[corner];
+                let cos = a.dot(&c) / (a.length() * c.length()).max(1e-12);
+                let angle = cos.clamp(-1.0, 1.0).acos();
+                corners[tri[corner]].push(Corner {
+                    triangle,
+                    corner,
+                    tangent: tangent.normalize() * angle,
+                    mirrored: normal.cross(&tangent).dot(&b) < 0.0,
+                });
+            }
+        }
+
+        let n = self.positions.len();
+        self.tangents = vec![Vec4::new(0.0, 0.0, 0.0, 1.0); n];
+        for (vertex, corners) in corners.iter().enumerate() {
+            // the unmirrored side keeps the vertex, a mirrored side gets a copy if both meet
+            let unmirrored = corners.iter().any(|c| !c.mirrored);
+            for mirrored in [false, true] {
+                let side: Vec<&Corner> =
+                    corners.iter().filter(|c| c.mirrored == mirrored).collect();
+                if side.is_empty() {
+                    continue;
+                }
+                let index = if mirrored && unmirrored {
+                    self.positions.push(self.positions[vertex]);
+                    self.normals.push(self.normals[vertex]);
+                    self.uvs.push(self.uvs[vertex]);
+                    self.colors.push(self.colors[vertex]);
+                    self.tangents.push(Vec4::new(0.0, 0.0, 0.0, 1.0));
+                    for c in &side {
+                        self.indices[c.triangle][c.corner] = self.positions.len() - 1;
+                    }
+                    self.positions.len() - 1
+                } else {
+                    vertex
+                };
+                let mut sum = Vec3::zero();
+                for c in &side {
+                    sum += c.tangent;
+                }
+                let w = if mirrored { -1.0 } else { 1.0 };
+                self.tangents[index] = project(sum, self.normals[vertex]).normalize().extend(w);
+            }
+        }
+        // vertices no triangle gave a usable tangent, any direction across the normal will do
+        for (i, tangent) in self.tangents.iter_mut().enumerate() {
+            let t = tangent.xyz();
+            if t.dot(&t) > 1e-12 && t.x.is_finite() {
+                continue;
+            }
+            let normal = self.normals[i];
+            let mut t = normal.cross(&Vec3::new(0.0, 0.0, 1.0));
+            if t.dot(&t) < 1e-12 {
+                t = normal.cross(&Vec3::new(1.0, 0.0, 0.0));
+            }
+            *tangent = t.normalize().extend(1.0);
+        }
+    }
+
+    // local space bounds, cheap enough but worth keeping around instead of redoing per frame
+    pub fn bounds(&self) -> Aabb {
+        Aabb::from_points(&self.positions).unwrap_or(Aabb::new(Vec3::zero(), Vec3::zero()))
+    }
+
+    pub fn bounding_sphere(&self) -> Sphere {
+        Sphere::from_points(&self.positions).unwrap_or(Sphere::new(Vec3::zero(), 0.0))
+    }
+
+    pub fn world_positions(&self, model: &Mat4) -> Vec<Vec3> {
+        self.positions
+            .iter()
+            .map(|p| (*model * p.extend(1.0)).xyz())
+            .collect()
+    }
+
+    // the vertex stage
+    pub fn vertices(&self, model: &Mat4, view_proj: &Mat4) -> Vec<Vertex> {
+        let mvp = *view_proj * *model;
+        let normal_matrix = Mat3::normal_matrix(model);
+        let tangent_matrix = Mat3::from_mat4(model);
+        (0..self.positions.len())
+            .map(|i| {
+                let p = self.positions[i].extend(1.0);
+                let mut varying = Varying::new(self.colors[i]);
+                varying.normal = (normal_matrix * self.normals[i]).normalize();
+                varying.world = (*model * p).xyz();
+                varying.uv = self.uvs[i];
+                if let Some(t) = self.tangents.get(i) {
+                    let tangent = (tangent_matrix * t.xyz()).normalize();
+                    varying.tangent = tangent.extend(t.w);
+                }
+                Vertex {
+                    position: mvp * p,
+                    varying,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for Mesh {
+    fn default() -> Mesh {
+        Mesh::new()
+    }
+}
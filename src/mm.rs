@@ -2,37 +2,145 @@
     matrix math library
     with matricies and vector 3 type
     some handy operations for vectors and matrix math
+    everything is generic over the scalar so big worlds can run in f64,
+    the short names are the f32 versions the renderer uses
 */
 
+use std::fmt::Debug;
 use std::ops::{
     Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign,
 };
 
+// what the vectors and matrices can be made of
+pub trait Scalar:
+    Copy
+    + Debug
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const INFINITY: Self;
+
+    fn from_f64(v: f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn sqrt(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn tan(self) -> Self;
+    fn acos(self) -> Self;
+    fn abs(self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn max(self, other: Self) -> Self;
+}
+
+macro_rules! impl_scalar {
+    ($($t:ty),*) => {$(
+        impl Scalar for $t {
+            const ZERO: $t = 0.0;
+            const ONE: $t = 1.0;
+            const INFINITY: $t = <$t>::INFINITY;
+
+            fn from_f64(v: f64) -> $t {
+                v as $t
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn sqrt(self) -> $t {
+                <$t>::sqrt(self)
+            }
+
+            fn sin(self) -> $t {
+                <$t>::sin(self)
+            }
+
+            fn cos(self) -> $t {
+                <$t>::cos(self)
+            }
+
+            fn tan(self) -> $t {
+                <$t>::tan(self)
+            }
+
+            fn acos(self) -> $t {
+                <$t>::acos(self)
+            }
+
+            fn abs(self) -> $t {
+                <$t>::abs(self)
+            }
+
+            fn min(self, other: $t) -> $t {
+                <$t>::min(self, other)
+            }
+
+            fn max(self, other: $t) -> $t {
+                <$t>::max(self, other)
+            }
+        }
+    )*};
+}
+
+impl_scalar!(f32, f64);
+
+pub type Vec2 = Vector2<f32>;
+pub type Vec3 = Vector3<f32>;
+pub type Vec4 = Vector4<f32>;
+pub type Mat2 = Matrix2<f32>;
+pub type Mat3 = Matrix3<f32>;
+pub type Mat4 = Matrix4<f32>;
+
+pub type DVec2 = Vector2<f64>;
+pub type DVec3 = Vector3<f64>;
+pub type DVec4 = Vector4<f64>;
+pub type DMat2 = Matrix2<f64>;
+pub type DMat3 = Matrix3<f64>;
+pub type DMat4 = Matrix4<f64>;
+pub type Quat = Quaternion<f32>;
+pub type DQuat = Quaternion<f64>;
+
 // vector 2 type
 #[derive(Debug, Copy, Clone)]
-pub struct Vec2 {
-    pub x: f32,
-    pub y: f32,
+pub struct Vector2<T> {
+    pub x: T,
+    pub y: T,
 }
 
-impl Vec2 {
-    pub fn new(x: f32, y: f32) -> Self {
+impl<T: Scalar> Vector2<T> {
+    pub fn new(x: T, y: T) -> Self {
         Self { x, y }
     }
 
     pub fn zero() -> Self {
-        Self { x: 0.0, y: 0.0 }
+        Self {
+            x: T::ZERO,
+            y: T::ZERO,
+        }
     }
 
     pub fn one() -> Self {
-        Self { x: 1.0, y: 1.0 }
+        Self {
+            x: T::ONE,
+            y: T::ONE,
+        }
     }
 
-    pub fn dot(&self, other: &Self) -> f32 {
+    pub fn dot(&self, other: &Self) -> T {
         self.x * other.x + self.y * other.y
     }
 
-    pub fn length(&self) -> f32 {
+    pub fn length(&self) -> T {
         self.dot(self).sqrt()
     }
 
@@ -44,15 +152,23 @@ impl Vec2 {
         }
     }
 
-    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+    pub fn lerp(&self, other: &Self, t: T) -> Self {
         Self {
             x: self.x + (other.x - self.x) * t,
             y: self.y + (other.y - self.y) * t,
         }
     }
+
+    // the same vector in another scalar type, like f32 to f64 and back
+    pub fn cast<U: Scalar>(&self) -> Vector2<U> {
+        Vector2 {
+            x: U::from_f64(self.x.to_f64()),
+            y: U::from_f64(self.y.to_f64()),
+        }
+    }
 }
 
-impl Add for Vec2 {
+impl<T: Scalar> Add for Vector2<T> {
     type Output = Self;
 
     fn add(self, other: Self) -> Self {
@@ -63,7 +179,7 @@ impl Add for Vec2 {
     }
 }
 
-impl Sub for Vec2 {
+impl<T: Scalar> Sub for Vector2<T> {
     type Output = Self;
 
     fn sub(self, other: Self) -> Self {
@@ -74,7 +190,7 @@ impl Sub for Vec2 {
     }
 }
 
-impl Mul for Vec2 {
+impl<T: Scalar> Mul for Vector2<T> {
     type Output = Self;
 
     fn mul(self, other: Self) -> Self {
@@ -85,7 +201,7 @@ impl Mul for Vec2 {
     }
 }
 
-impl Div for Vec2 {
+impl<T: Scalar> Div for Vector2<T> {
     type Output = Self;
 
     fn div(self, other: Self) -> Self {
@@ -96,38 +212,38 @@ impl Div for Vec2 {
     }
 }
 
-impl AddAssign for Vec2 {
+impl<T: Scalar> AddAssign for Vector2<T> {
     fn add_assign(&mut self, other: Self) {
         self.x += other.x;
         self.y += other.y;
     }
 }
 
-impl SubAssign for Vec2 {
+impl<T: Scalar> SubAssign for Vector2<T> {
     fn sub_assign(&mut self, other: Self) {
         self.x -= other.x;
         self.y -= other.y;
     }
 }
 
-impl MulAssign for Vec2 {
+impl<T: Scalar> MulAssign for Vector2<T> {
     fn mul_assign(&mut self, other: Self) {
         self.x *= other.x;
         self.y *= other.y;
     }
 }
 
-impl DivAssign for Vec2 {
+impl<T: Scalar> DivAssign for Vector2<T> {
     fn div_assign(&mut self, other: Self) {
         self.x /= other.x;
         self.y /= other.y;
     }
 }
 
-impl Index<usize> for Vec2 {
-    type Output = f32;
+impl<T: Scalar> Index<usize> for Vector2<T> {
+    type Output = T;
 
-    fn index(&self, index: usize) -> &f32 {
+    fn index(&self, index: usize) -> &T {
         match index {
             0 => &self.x,
             1 => &self.y,
@@ -136,8 +252,8 @@ impl Index<usize> for Vec2 {
     }
 }
 
-impl IndexMut<usize> for Vec2 {
-    fn index_mut(&mut self, index: usize) -> &mut f32 {
+impl<T: Scalar> IndexMut<usize> for Vector2<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
         match index {
             0 => &mut self.x,
             1 => &mut self.y,
@@ -146,10 +262,10 @@ impl IndexMut<usize> for Vec2 {
     }
 }
 
-impl Mul<f32> for Vec2 {
+impl<T: Scalar> Mul<T> for Vector2<T> {
     type Output = Self;
 
-    fn mul(self, other: f32) -> Self {
+    fn mul(self, other: T) -> Self {
         Self {
             x: self.x * other,
             y: self.y * other,
@@ -157,10 +273,10 @@ impl Mul<f32> for Vec2 {
     }
 }
 
-impl Div<f32> for Vec2 {
+impl<T: Scalar> Div<T> for Vector2<T> {
     type Output = Self;
 
-    fn div(self, other: f32) -> Self {
+    fn div(self, other: T) -> Self {
         Self {
             x: self.x / other,
             y: self.y / other,
@@ -168,60 +284,38 @@ impl Div<f32> for Vec2 {
     }
 }
 
-impl Mul<Vec2> for f32 {
-    type Output = Vec2;
-
-    fn mul(self, other: Vec2) -> Vec2 {
-        Vec2 {
-            x: self * other.x,
-            y: self * other.y,
-        }
-    }
-}
-
-impl Div<Vec2> for f32 {
-    type Output = Vec2;
-
-    fn div(self, other: Vec2) -> Vec2 {
-        Vec2 {
-            x: self / other.x,
-            y: self / other.y,
-        }
-    }
-}
-
-impl AddAssign<f32> for Vec2 {
-    fn add_assign(&mut self, other: f32) {
+impl<T: Scalar> AddAssign<T> for Vector2<T> {
+    fn add_assign(&mut self, other: T) {
         self.x += other;
         self.y += other;
     }
 }
 
-impl SubAssign<f32> for Vec2 {
-    fn sub_assign(&mut self, other: f32) {
+impl<T: Scalar> SubAssign<T> for Vector2<T> {
+    fn sub_assign(&mut self, other: T) {
         self.x -= other;
         self.y -= other;
     }
 }
 
-impl MulAssign<f32> for Vec2 {
-    fn mul_assign(&mut self, other: f32) {
+impl<T: Scalar> MulAssign<T> for Vector2<T> {
+    fn mul_assign(&mut self, other: T) {
         self.x *= other;
         self.y *= other;
     }
 }
 
-impl DivAssign<f32> for Vec2 {
-    fn div_assign(&mut self, other: f32) {
+impl<T: Scalar> DivAssign<T> for Vector2<T> {
+    fn div_assign(&mut self, other: T) {
         self.x /= other;
         self.y /= other;
     }
 }
 
-impl Add<f32> for Vec2 {
+impl<T: Scalar> Add<T> for Vector2<T> {
     type Output = Self;
 
-    fn add(self, other: f32) -> Self {
+    fn add(self, other: T) -> Self {
         Self {
             x: self.x + other,
             y: self.y + other,
@@ -229,10 +323,10 @@ impl Add<f32> for Vec2 {
     }
 }
 
-impl Sub<f32> for Vec2 {
+impl<T: Scalar> Sub<T> for Vector2<T> {
     type Output = Self;
 
-    fn sub(self, other: f32) -> Self {
+    fn sub(self, other: T) -> Self {
         Self {
             x: self.x - other,
             y: self.y - other,
@@ -240,7 +334,7 @@ impl Sub<f32> for Vec2 {
     }
 }
 
-impl Neg for Vec2 {
+impl<T: Scalar> Neg for Vector2<T> {
     type Output = Self;
 
     fn neg(self) -> Self {
@@ -253,66 +347,103 @@ impl Neg for Vec2 {
 
 // Vector 3 type
 #[derive(Debug, Copy, Clone)]
-pub struct Vec3 {
-    pub x: f32,
-    pub y: f32,
-    pub z: f32,
+pub struct Vector3<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
 }
 
 // Vector 3 operations
-impl Vec3 {
-    pub fn new(x: f32, y: f32, z: f32) -> Vec3 {
-        Vec3 { x, y, z }
+impl<T: Scalar> Vector3<T> {
+    pub fn new(x: T, y: T, z: T) -> Vector3<T> {
+        Vector3 { x, y, z }
     }
 
-    pub fn one() -> Vec3 {
-        Vec3 {
-            x: 1.0,
-            y: 1.0,
-            z: 1.0,
+    pub fn one() -> Vector3<T> {
+        Vector3 {
+            x: T::ONE,
+            y: T::ONE,
+            z: T::ONE,
         }
     }
 
-    pub fn zero() -> Vec3 {
-        Vec3 {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
+    pub fn zero() -> Vector3<T> {
+        Vector3 {
+            x: T::ZERO,
+            y: T::ZERO,
+            z: T::ZERO,
         }
     }
 
-    pub fn dot(&self, other: &Vec3) -> f32 {
+    pub fn dot(&self, other: &Vector3<T>) -> T {
         self.x * other.x + self.y * other.y + self.z * other.z
     }
 
-    pub fn cross(&self, other: &Vec3) -> Vec3 {
-        Vec3 {
+    pub fn cross(&self, other: &Vector3<T>) -> Vector3<T> {
+        Vector3 {
             x: self.y * other.z - self.z * other.y,
             y: self.z * other.x - self.x * other.z,
             z: self.x * other.y - self.y * other.x,
         }
     }
 
-    pub fn length(&self) -> f32 {
+    pub fn length(&self) -> T {
         self.dot(self).sqrt()
     }
 
-    pub fn normalize(&self) -> Vec3 {
+    pub fn normalize(&self) -> Vector3<T> {
         let len = self.length();
-        Vec3 {
+        Vector3 {
             x: self.x / len,
             y: self.y / len,
             z: self.z / len,
         }
     }
+
+    pub fn lerp(&self, other: &Vector3<T>, t: T) -> Vector3<T> {
+        Vector3 {
+            x: self.x + (other.x - self.x) * t,
+            y: self.y + (other.y - self.y) * t,
+            z: self.z + (other.z - self.z) * t,
+        }
+    }
+
+    // componentwise
+    pub fn min(&self, other: &Vector3<T>) -> Vector3<T> {
+        Vector3 {
+            x: self.x.min(other.x),
+            y: self.y.min(other.y),
+            z: self.z.min(other.z),
+        }
+    }
+
+    pub fn max(&self, other: &Vector3<T>) -> Vector3<T> {
+        Vector3 {
+            x: self.x.max(other.x),
+            y: self.y.max(other.y),
+            z: self.z.max(other.z),
+        }
+    }
+
+    pub fn extend(&self, w: T) -> Vector4<T> {
+        Vector4::new(self.x, self.y, self.z, w)
+    }
+
+    pub fn cast<U: Scalar>(&self) -> Vector3<U> {
+        Vector3 {
+            x: U::from_f64(self.x.to_f64()),
+            y: U::from_f64(self.y.to_f64()),
+            z: U::from_f64(self.z.to_f64()),
+        }
+    }
 }
 
 // Vector 3 operations
-impl Add for Vec3 {
-    type Output = Vec3;
+impl<T: Scalar> Add for Vector3<T> {
+    type Output = Vector3<T>;
 
-    fn add(self, other: Vec3) -> Vec3 {
-        Vec3 {
+    fn add(self, other: Vector3<T>) -> Vector3<T> {
+        Vector3 {
             x: self.x + other.x,
             y: self.y + other.y,
             z: self.z + other.z,
@@ -320,11 +451,11 @@ impl Add for Vec3 {
     }
 }
 
-impl Sub for Vec3 {
-    type Output = Vec3;
+impl<T: Scalar> Sub for Vector3<T> {
+    type Output = Vector3<T>;
 
-    fn sub(self, other: Vec3) -> Vec3 {
-        Vec3 {
+    fn sub(self, other: Vector3<T>) -> Vector3<T> {
+        Vector3 {
             x: self.x - other.x,
             y: self.y - other.y,
             z: self.z - other.z,
@@ -332,11 +463,11 @@ impl Sub for Vec3 {
     }
 }
 
-impl Neg for Vec3 {
-    type Output = Vec3;
+impl<T: Scalar> Neg for Vector3<T> {
+    type Output = Vector3<T>;
 
-    fn neg(self) -> Vec3 {
-        Vec3 {
+    fn neg(self) -> Vector3<T> {
+        Vector3 {
             x: -self.x,
             y: -self.y,
             z: -self.z,
@@ -344,11 +475,11 @@ impl Neg for Vec3 {
     }
 }
 
-impl Mul for Vec3 {
-    type Output = Vec3;
+impl<T: Scalar> Mul for Vector3<T> {
+    type Output = Vector3<T>;
 
-    fn mul(self, other: Vec3) -> Vec3 {
-        Vec3 {
+    fn mul(self, other: Vector3<T>) -> Vector3<T> {
+        Vector3 {
             x: self.x * other.x,
             y: self.y * other.y,
             z: self.z * other.z,
@@ -356,11 +487,11 @@ impl Mul for Vec3 {
     }
 }
 
-impl Div for Vec3 {
-    type Output = Vec3;
+impl<T: Scalar> Div for Vector3<T> {
+    type Output = Vector3<T>;
 
-    fn div(self, other: Vec3) -> Vec3 {
-        Vec3 {
+    fn div(self, other: Vector3<T>) -> Vector3<T> {
+        Vector3 {
             x: self.x / other.x,
             y: self.y / other.y,
             z: self.z / other.z,
@@ -368,11 +499,11 @@ impl Div for Vec3 {
     }
 }
 
-impl Mul<f32> for Vec3 {
-    type Output = Vec3;
+impl<T: Scalar> Mul<T> for Vector3<T> {
+    type Output = Vector3<T>;
 
-    fn mul(self, other: f32) -> Vec3 {
-        Vec3 {
+    fn mul(self, other: T) -> Vector3<T> {
+        Vector3 {
             x: self.x * other,
             y: self.y * other,
             z: self.z * other,
@@ -380,11 +511,11 @@ impl Mul<f32> for Vec3 {
     }
 }
 
-impl Div<f32> for Vec3 {
-    type Output = Vec3;
+impl<T: Scalar> Div<T> for Vector3<T> {
+    type Output = Vector3<T>;
 
-    fn div(self, other: f32) -> Vec3 {
-        Vec3 {
+    fn div(self, other: T) -> Vector3<T> {
+        Vector3 {
             x: self.x / other,
             y: self.y / other,
             z: self.z / other,
@@ -392,71 +523,100 @@ impl Div<f32> for Vec3 {
     }
 }
 
-impl Add<Vec3> for f32 {
-    type Output = Vec3;
+// scalar on the left hand side, the orphan rules want these spelled out per scalar type
+macro_rules! scalar_lhs_ops {
+    ($($t:ty),*) => {$(
+        impl Mul<Vector2<$t>> for $t {
+            type Output = Vector2<$t>;
+
+            fn mul(self, other: Vector2<$t>) -> Vector2<$t> {
+                Vector2 {
+                    x: self * other.x,
+                    y: self * other.y,
+                }
+            }
+        }
+
+        impl Div<Vector2<$t>> for $t {
+            type Output = Vector2<$t>;
 
-    fn add(self, other: Vec3) -> Vec3 {
-        Vec3 {
-            x: self + other.x,
-            y: self + other.y,
-            z: self + other.z,
+            fn div(self, other: Vector2<$t>) -> Vector2<$t> {
+                Vector2 {
+                    x: self / other.x,
+                    y: self / other.y,
+                }
+            }
         }
-    }
-}
 
-impl Sub<Vec3> for f32 {
-    type Output = Vec3;
+        impl Add<Vector3<$t>> for $t {
+            type Output = Vector3<$t>;
 
-    fn sub(self, other: Vec3) -> Vec3 {
-        Vec3 {
-            x: self - other.x,
-            y: self - other.y,
-            z: self - other.z,
+            fn add(self, other: Vector3<$t>) -> Vector3<$t> {
+                Vector3 {
+                    x: self + other.x,
+                    y: self + other.y,
+                    z: self + other.z,
+                }
+            }
         }
-    }
+
+        impl Sub<Vector3<$t>> for $t {
+            type Output = Vector3<$t>;
+
+            fn sub(self, other: Vector3<$t>) -> Vector3<$t> {
+                Vector3 {
+                    x: self - other.x,
+                    y: self - other.y,
+                    z: self - other.z,
+                }
+            }
+        }
+    )*};
 }
 
+scalar_lhs_ops!(f32, f64);
+
 // impl add assign
-impl AddAssign for Vec3 {
-    fn add_assign(&mut self, other: Vec3) {
+impl<T: Scalar> AddAssign for Vector3<T> {
+    fn add_assign(&mut self, other: Vector3<T>) {
         *self = *self + other;
     }
 }
 
-impl SubAssign for Vec3 {
-    fn sub_assign(&mut self, other: Vec3) {
+impl<T: Scalar> SubAssign for Vector3<T> {
+    fn sub_assign(&mut self, other: Vector3<T>) {
         *self = *self - other;
     }
 }
 
-impl MulAssign for Vec3 {
-    fn mul_assign(&mut self, other: Vec3) {
+impl<T: Scalar> MulAssign for Vector3<T> {
+    fn mul_assign(&mut self, other: Vector3<T>) {
         *self = *self * other;
     }
 }
 
-impl DivAssign for Vec3 {
-    fn div_assign(&mut self, other: Vec3) {
+impl<T: Scalar> DivAssign for Vector3<T> {
+    fn div_assign(&mut self, other: Vector3<T>) {
         *self = *self / other;
     }
 }
 
-impl MulAssign<f32> for Vec3 {
-    fn mul_assign(&mut self, other: f32) {
+impl<T: Scalar> MulAssign<T> for Vector3<T> {
+    fn mul_assign(&mut self, other: T) {
         *self = *self * other;
     }
 }
 
-impl DivAssign<f32> for Vec3 {
-    fn div_assign(&mut self, other: f32) {
+impl<T: Scalar> DivAssign<T> for Vector3<T> {
+    fn div_assign(&mut self, other: T) {
         *self = *self / other;
     }
 }
 
-impl Index<usize> for Vec3 {
-    type Output = f32;
+impl<T: Scalar> Index<usize> for Vector3<T> {
+    type Output = T;
 
-    fn index(&self, index: usize) -> &f32 {
+    fn index(&self, index: usize) -> &T {
         match index {
             0 => &self.x,
             1 => &self.y,
@@ -466,8 +626,8 @@ impl Index<usize> for Vec3 {
     }
 }
 
-impl IndexMut<usize> for Vec3 {
-    fn index_mut(&mut self, index: usize) -> &mut f32 {
+impl<T: Scalar> IndexMut<usize> for Vector3<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
         match index {
             0 => &mut self.x,
             1 => &mut self.y,
@@ -477,31 +637,146 @@ impl IndexMut<usize> for Vec3 {
     }
 }
 
+// Vector 4 type, mostly for homogeneous clip space coordinates
+#[derive(Debug, Copy, Clone)]
+pub struct Vector4<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+    pub w: T,
+}
+
+impl<T: Scalar> Vector4<T> {
+    pub fn new(x: T, y: T, z: T, w: T) -> Vector4<T> {
+        Vector4 { x, y, z, w }
+    }
+
+    pub fn zero() -> Vector4<T> {
+        Vector4 {
+            x: T::ZERO,
+            y: T::ZERO,
+            z: T::ZERO,
+            w: T::ZERO,
+        }
+    }
+
+    pub fn dot(&self, other: &Vector4<T>) -> T {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn xyz(&self) -> Vector3<T> {
+        Vector3::new(self.x, self.y, self.z)
+    }
+
+    pub fn lerp(&self, other: &Vector4<T>, t: T) -> Vector4<T> {
+        Vector4 {
+            x: self.x + (other.x - self.x) * t,
+            y: self.y + (other.y - self.y) * t,
+            z: self.z + (other.z - self.z) * t,
+            w: self.w + (other.w - self.w) * t,
+        }
+    }
+
+    pub fn cast<U: Scalar>(&self) -> Vector4<U> {
+        Vector4 {
+            x: U::from_f64(self.x.to_f64()),
+            y: U::from_f64(self.y.to_f64()),
+            z: U::from_f64(self.z.to_f64()),
+            w: U::from_f64(self.w.to_f64()),
+        }
+    }
+}
+
+impl<T: Scalar> Add for Vector4<T> {
+    type Output = Vector4<T>;
+
+    fn add(self, other: Vector4<T>) -> Vector4<T> {
+        Vector4 {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+            w: self.w + other.w,
+        }
+    }
+}
+
+impl<T: Scalar> Sub for Vector4<T> {
+    type Output = Vector4<T>;
+
+    fn sub(self, other: Vector4<T>) -> Vector4<T> {
+        Vector4 {
+            x: self.x - other.x,
+            y: self.y - other.y,
+            z: self.z - other.z,
+            w: self.w - other.w,
+        }
+    }
+}
+
+impl<T: Scalar> Mul<T> for Vector4<T> {
+    type Output = Vector4<T>;
+
+    fn mul(self, other: T) -> Vector4<T> {
+        Vector4 {
+            x: self.x * other,
+            y: self.y * other,
+            z: self.z * other,
+            w: self.w * other,
+        }
+    }
+}
+
+impl<T: Scalar> Index<usize> for Vector4<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        match index {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            3 => &self.w,
+            _ => panic!("index out of bounds"),
+        }
+    }
+}
+
+impl<T: Scalar> IndexMut<usize> for Vector4<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        match index {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            3 => &mut self.w,
+            _ => panic!("index out of bounds"),
+        }
+    }
+}
+
 // Matrix 4x4 type
 #[derive(Debug, Copy, Clone)]
-pub struct Mat4 {
-    pub m: [[f32; 4]; 4],
+pub struct Matrix4<T> {
+    pub m: [[T; 4]; 4],
 }
 
 // Matrix 4x4 operations
-impl Mat4 {
-    pub fn new(m: [[f32; 4]; 4]) -> Mat4 {
-        Mat4 { m }
+impl<T: Scalar> Matrix4<T> {
+    pub fn new(m: [[T; 4]; 4]) -> Matrix4<T> {
+        Matrix4 { m }
     }
 
-    pub fn identity() -> Mat4 {
-        Mat4 {
+    pub fn identity() -> Matrix4<T> {
+        Matrix4 {
             m: [
-                [1.0, 0.0, 0.0, 0.0],
-                [0.0, 1.0, 0.0, 0.0],
-                [0.0, 0.0, 1.0, 0.0],
-                [0.0, 0.0, 0.0, 1.0],
+                [T::ONE, T::ZERO, T::ZERO, T::ZERO],
+                [T::ZERO, T::ONE, T::ZERO, T::ZERO],
+                [T::ZERO, T::ZERO, T::ONE, T::ZERO],
+                [T::ZERO, T::ZERO, T::ZERO, T::ONE],
             ],
         }
     }
 
-    pub fn transpose(&self) -> Mat4 {
-        Mat4 {
+    pub fn transpose(&self) -> Matrix4<T> {
+        Matrix4 {
             m: [
                 [self.m[0][0], self.m[1][0], self.m[2][0], self.m[3][0]],
                 [self.m[0][1], self.m[1][1], self.m[2][1], self.m[3][1]],
@@ -511,155 +786,197 @@ impl Mat4 {
         }
     }
 
-    pub fn rotation_x(angle: f32) -> Mat4 {
+    // gauss-jordan with partial pivoting, None if the matrix is singular
+    pub fn inverse(&self) -> Option<Matrix4<T>> {
+        let mut a = self.m;
+        let mut inv = Matrix4::identity().m;
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| {
+                    a[i][col]
+                        .abs()
+                        .partial_cmp(&a[j][col].abs())
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap();
+            if a[pivot][col].abs() < T::from_f64(1e-12) {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+            let scale = T::ONE / a[col][col];
+            for k in 0..4 {
+                a[col][k] *= scale;
+                inv[col][k] *= scale;
+            }
+            for row in 0..4 {
+                if row == col {
+                    continue;
+                }
+                let factor = a[row][col];
+                for k in 0..4 {
+                    a[row][k] -= factor * a[col][k];
+                    inv[row][k] -= factor * inv[col][k];
+                }
+            }
+        }
+        Some(Matrix4 { m: inv })
+    }
+
+    pub fn rotation_x(angle: T) -> Matrix4<T> {
         let s = angle.sin();
         let c = angle.cos();
-        Mat4 {
+        Matrix4 {
             m: [
-                [1.0, 0.0, 0.0, 0.0],
-                [0.0, c, -s, 0.0],
-                [0.0, s, c, 0.0],
-                [0.0, 0.0, 0.0, 1.0],
+                [T::ONE, T::ZERO, T::ZERO, T::ZERO],
+                [T::ZERO, c, -s, T::ZERO],
+                [T::ZERO, s, c, T::ZERO],
+                [T::ZERO, T::ZERO, T::ZERO, T::ONE],
             ],
         }
     }
 
-    pub fn rotation_y(angle: f32) -> Mat4 {
+    pub fn rotation_y(angle: T) -> Matrix4<T> {
         let s = angle.sin();
         let c = angle.cos();
-        Mat4 {
+        Matrix4 {
             m: [
-                [c, 0.0, s, 0.0],
-                [0.0, 1.0, 0.0, 0.0],
-                [-s, 0.0, c, 0.0],
-                [0.0, 0.0, 0.0, 1.0],
+                [c, T::ZERO, s, T::ZERO],
+                [T::ZERO, T::ONE, T::ZERO, T::ZERO],
+                [-s, T::ZERO, c, T::ZERO],
+                [T::ZERO, T::ZERO, T::ZERO, T::ONE],
             ],
         }
     }
 
-    pub fn rotation_z(angle: f32) -> Mat4 {
+    pub fn rotation_z(angle: T) -> Matrix4<T> {
         let s = angle.sin();
         let c = angle.cos();
-        Mat4 {
+        Matrix4 {
             m: [
-                [c, -s, 0.0, 0.0],
-                [s, c, 0.0, 0.0],
-                [0.0, 0.0, 1.0, 0.0],
-                [0.0, 0.0, 0.0, 1.0],
+                [c, -s, T::ZERO, T::ZERO],
+                [s, c, T::ZERO, T::ZERO],
+                [T::ZERO, T::ZERO, T::ONE, T::ZERO],
+                [T::ZERO, T::ZERO, T::ZERO, T::ONE],
             ],
         }
     }
 
-    pub fn rotation(angle: f32, axis: Vec3) -> Mat4 {
+    pub fn rotation(angle: T, axis: Vector3<T>) -> Matrix4<T> {
         let a = axis.normalize();
         let s = angle.sin();
         let c = angle.cos();
-        let t = 1.0 - c;
-        Mat4 {
+        let t = T::ONE - c;
+        Matrix4 {
             m: [
                 [
                     t * a.x * a.x + c,
                     t * a.x * a.y - s * a.z,
                     t * a.x * a.z + s * a.y,
-                    0.0,
+                    T::ZERO,
                 ],
                 [
                     t * a.x * a.y + s * a.z,
                     t * a.y * a.y + c,
                     t * a.y * a.z - s * a.x,
-                    0.0,
+                    T::ZERO,
                 ],
                 [
                     t * a.x * a.z - s * a.y,
                     t * a.y * a.z + s * a.x,
                     t * a.z * a.z + c,
-                    0.0,
+                    T::ZERO,
                 ],
-                [0.0, 0.0, 0.0, 1.0],
+                [T::ZERO, T::ZERO, T::ZERO, T::ONE],
             ],
         }
     }
 
-    pub fn translation(translation: Vec3) -> Mat4 {
-        Mat4 {
+    pub fn translation(translation: Vector3<T>) -> Matrix4<T> {
+        Matrix4 {
             m: [
-                [1.0, 0.0, 0.0, translation.x],
-                [0.0, 1.0, 0.0, translation.y],
-                [0.0, 0.0, 1.0, translation.z],
-                [0.0, 0.0, 0.0, 1.0],
+                [T::ONE, T::ZERO, T::ZERO, translation.x],
+                [T::ZERO, T::ONE, T::ZERO, translation.y],
+                [T::ZERO, T::ZERO, T::ONE, translation.z],
+                [T::ZERO, T::ZERO, T::ZERO, T::ONE],
             ],
         }
     }
 
-    pub fn scale(scale: Vec3) -> Mat4 {
-        Mat4 {
+    pub fn scale(scale: Vector3<T>) -> Matrix4<T> {
+        Matrix4 {
             m: [
-                [scale.x, 0.0, 0.0, 0.0],
-                [0.0, scale.y, 0.0, 0.0],
-                [0.0, 0.0, scale.z, 0.0],
-                [0.0, 0.0, 0.0, 1.0],
+                [scale.x, T::ZERO, T::ZERO, T::ZERO],
+                [T::ZERO, scale.y, T::ZERO, T::ZERO],
+                [T::ZERO, T::ZERO, scale.z, T::ZERO],
+                [T::ZERO, T::ZERO, T::ZERO, T::ONE],
             ],
         }
     }
 
-    pub fn perspective(fov: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
-        let f = 1.0 / (fov / 2.0).tan();
-        Mat4 {
+    pub fn perspective(fov: T, aspect: T, near: T, far: T) -> Matrix4<T> {
+        let f = T::ONE / (fov / T::from_f64(2.0)).tan();
+        Matrix4 {
             m: [
-                [f / aspect, 0.0, 0.0, 0.0],
-                [0.0, f, 0.0, 0.0],
+                [f / aspect, T::ZERO, T::ZERO, T::ZERO],
+                [T::ZERO, f, T::ZERO, T::ZERO],
                 [
-                    0.0,
-                    0.0,
+                    T::ZERO,
+                    T::ZERO,
                     (far + near) / (near - far),
-                    (2.0 * far * near) / (near - far),
+                    (T::from_f64(2.0) * far * near) / (near - far),
                 ],
-                [0.0, 0.0, -1.0, 0.0],
+                [T::ZERO, T::ZERO, -T::ONE, T::ZERO],
             ],
         }
     }
 
-    pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Mat4 {
-        Mat4 {
+    pub fn orthographic(left: T, right: T, bottom: T, top: T, near: T, far: T) -> Matrix4<T> {
+        Matrix4 {
             m: [
                 [
-                    2.0 / (right - left),
-                    0.0,
-                    0.0,
+                    T::from_f64(2.0) / (right - left),
+                    T::ZERO,
+                    T::ZERO,
                     (left + right) / (left - right),
                 ],
                 [
-                    0.0,
-                    2.0 / (top - bottom),
-                    0.0,
+                    T::ZERO,
+                    T::from_f64(2.0) / (top - bottom),
+                    T::ZERO,
                     (bottom + top) / (bottom - top),
                 ],
-                [0.0, 0.0, 2.0 / (near - far), (near + far) / (near - far)],
-                [0.0, 0.0, 0.0, 1.0],
+                [
+                    T::ZERO,
+                    T::ZERO,
+                    T::from_f64(2.0) / (near - far),
+                    (near + far) / (near - far),
+                ],
+                [T::ZERO, T::ZERO, T::ZERO, T::ONE],
             ],
         }
     }
 
-    pub fn look_at(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
-        // A direct translation of the GLM implementation
+    pub fn look_at(eye: Vector3<T>, center: Vector3<T>, up: Vector3<T>) -> Matrix4<T> {
+        // same as GLM, transposed to match the row major layout used here
         let f = (center - eye).normalize();
         let s = f.cross(&up).normalize();
         let u = s.cross(&f);
-        Mat4 {
+        Matrix4 {
             m: [
-                [s.x, u.x, -f.x, 0.0],
-                [s.y, u.y, -f.y, 0.0],
-                [s.z, u.z, -f.z, 0.0],
-                [-s.dot(&eye), -u.dot(&eye), f.dot(&eye), 1.0],
+                [s.x, s.y, s.z, -s.dot(&eye)],
+                [u.x, u.y, u.z, -u.dot(&eye)],
+                [-f.x, -f.y, -f.z, f.dot(&eye)],
+                [T::ZERO, T::ZERO, T::ZERO, T::ONE],
             ],
         }
     }
 
-    pub fn to_array(&self) -> [[f32; 4]; 4] {
+    pub fn to_array(&self) -> [[T; 4]; 4] {
         self.m
     }
 
-    pub fn to_vec(&self) -> Vec<f32> {
+    pub fn to_vec(&self) -> Vec<T> {
         vec![
             self.m[0][0],
             self.m[0][1],
@@ -680,37 +997,1102 @@ impl Mat4 {
         ]
     }
 
-    pub fn grotation(x: f32, y: f32, z: f32) -> Mat4 {
-        Mat4::rotation_x(x) * Mat4::rotation_y(y) * Mat4::rotation_z(z)
+    pub fn grotation(x: T, y: T, z: T) -> Matrix4<T> {
+        Matrix4::rotation_x(x) * Matrix4::rotation_y(y) * Matrix4::rotation_z(z)
+    }
+
+    pub fn cast<U: Scalar>(&self) -> Matrix4<U> {
+        Matrix4 {
+            m: self.m.map(|row| row.map(|v| U::from_f64(v.to_f64()))),
+        }
     }
 }
 
-impl Mul for Mat4 {
-    type Output = Mat4;
+impl<T: Scalar> Mul for Matrix4<T> {
+    type Output = Matrix4<T>;
 
-    fn mul(self, rhs: Mat4) -> Mat4 {
-        let mut m = [[0.0; 4]; 4];
-        for i in 0..4 {
-            for j in 0..4 {
-                m[i][j] = self.m[i][0] * rhs.m[0][j]
+    fn mul(self, rhs: Matrix4<T>) -> Matrix4<T> {
+        let m = std::array::from_fn(|i| {
+            std::array::from_fn(|j| {
+                self.m[i][0] * rhs.m[0][j]
                     + self.m[i][1] * rhs.m[1][j]
                     + self.m[i][2] * rhs.m[2][j]
-                    + self.m[i][3] * rhs.m[3][j];
+                    + self.m[i][3] * rhs.m[3][j]
+            })
+        });
+        Matrix4 { m }
+    }
+}
+
+// implement multiplication for vec3 * mat4
+impl<T: Scalar> Mul<Matrix4<T>> for Vector3<T> {
+    type Output = Vector3<T>;
+
+    fn mul(self, rhs: Matrix4<T>) -> Vector3<T> {
+        let v: [T; 3] = std::array::from_fn(|i| {
+            self.x * rhs.m[0][i] + self.y * rhs.m[1][i] + self.z * rhs.m[2][i] + rhs.m[3][i]
+        });
+        Vector3::new(v[0], v[1], v[2])
+    }
+}
+
+// implement multiplication for mat4 * vec4, treating the vector as a column
+impl<T: Scalar> Mul<Vector4<T>> for Matrix4<T> {
+    type Output = Vector4<T>;
+
+    fn mul(self, rhs: Vector4<T>) -> Vector4<T> {
+        let mut v = [T::ZERO; 4];
+        for (i, row) in self.m.iter().enumerate() {
+            v[i] = row[0] * rhs.x + row[1] * rhs.y + row[2] * rhs.z + row[3] * rhs.w;
+        }
+        Vector4::new(v[0], v[1], v[2], v[3])
+    }
+}
+
+// Matrix 3x3 type
+#[derive(Debug, Copy, Clone)]
+pub struct Matrix3<T> {
+    pub m: [[T; 3]; 3],
+}
+
+// Matrix 3x3 operations
+impl<T: Scalar> Matrix3<T> {
+    pub fn new(m: [[T; 3]; 3]) -> Matrix3<T> {
+        Matrix3 { m }
+    }
+
+    pub fn identity() -> Matrix3<T> {
+        Matrix3 {
+            m: [
+                [T::ONE, T::ZERO, T::ZERO],
+                [T::ZERO, T::ONE, T::ZERO],
+                [T::ZERO, T::ZERO, T::ONE],
+            ],
+        }
+    }
+
+    // basis vectors as columns, like a tangent frame
+    pub fn from_cols(x: Vector3<T>, y: Vector3<T>, z: Vector3<T>) -> Matrix3<T> {
+        Matrix3 {
+            m: [[x.x, y.x, z.x], [x.y, y.y, z.y], [x.z, y.z, z.z]],
+        }
+    }
+
+    pub fn transpose(&self) -> Matrix3<T> {
+        let mut m = [[T::ZERO; 3]; 3];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = self.m[j][i];
+            }
+        }
+        Matrix3 { m }
+    }
+
+    pub fn determinant(&self) -> T {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    // adjugate over determinant, None when the matrix is singular
+    pub fn inverse(&self) -> Option<Matrix3<T>> {
+        let det = self.determinant();
+        if det.abs() < T::from_f64(1e-12) {
+            return None;
+        }
+        let m = &self.m;
+        let inv_det = T::ONE / det;
+        let mut inv = [[T::ZERO; 3]; 3];
+        for (i, row) in inv.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                // cofactor of the transposed position, the indices wrap so the signs work out
+                let (r0, r1) = ((j + 1) % 3, (j + 2) % 3);
+                let (c0, c1) = ((i + 1) % 3, (i + 2) % 3);
+                *v = (m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]) * inv_det;
             }
         }
-        Mat4 { m }
+        Some(Matrix3 { m: inv })
+    }
+
+    // the upper left 3x3, drops any translation
+    pub fn from_mat4(mat: &Matrix4<T>) -> Matrix3<T> {
+        let mut m = [[T::ZERO; 3]; 3];
+        for (i, row) in m.iter_mut().enumerate() {
+            row.copy_from_slice(&mat.m[i][..3]);
+        }
+        Matrix3 { m }
+    }
+
+    pub fn to_mat4(&self) -> Matrix4<T> {
+        let mut m = Matrix4::identity().m;
+        for (i, row) in self.m.iter().enumerate() {
+            m[i][..3].copy_from_slice(row);
+        }
+        Matrix4 { m }
+    }
+
+    // inverse transpose of the model's upper 3x3, keeps normals perpendicular under
+    // non uniform scales. falls back to the plain 3x3 if the model is singular
+    pub fn normal_matrix(model: &Matrix4<T>) -> Matrix3<T> {
+        let m = Matrix3::from_mat4(model);
+        m.inverse().map(|inv| inv.transpose()).unwrap_or(m)
+    }
+
+    // 2d homogeneous transforms, points are (x, y, 1)
+    pub fn translation_2d(translation: Vector2<T>) -> Matrix3<T> {
+        Matrix3 {
+            m: [
+                [T::ONE, T::ZERO, translation.x],
+                [T::ZERO, T::ONE, translation.y],
+                [T::ZERO, T::ZERO, T::ONE],
+            ],
+        }
+    }
+
+    pub fn rotation_2d(angle: T) -> Matrix3<T> {
+        Matrix2::rotation(angle).to_mat3()
+    }
+
+    pub fn scale_2d(scale: Vector2<T>) -> Matrix3<T> {
+        Matrix2::scale(scale).to_mat3()
+    }
+
+    // transform a 2d point, including translation and the divide
+    pub fn transform_point(&self, p: Vector2<T>) -> Vector2<T> {
+        let v = *self * Vector3::new(p.x, p.y, T::ONE);
+        Vector2::new(v.x, v.y) / v.z
+    }
+
+    // transform a 2d direction, translation does not apply
+    pub fn transform_vector(&self, d: Vector2<T>) -> Vector2<T> {
+        let v = *self * Vector3::new(d.x, d.y, T::ZERO);
+        Vector2::new(v.x, v.y)
+    }
+
+    pub fn cast<U: Scalar>(&self) -> Matrix3<U> {
+        Matrix3 {
+            m: self.m.map(|row| row.map(|v| U::from_f64(v.to_f64()))),
+        }
     }
 }
 
-// implement multiplication for vec3 * mat4
-impl Mul<Mat4> for Vec3 {
-    type Output = Vec3;
+impl<T: Scalar> Mul for Matrix3<T> {
+    type Output = Matrix3<T>;
+
+    fn mul(self, rhs: Matrix3<T>) -> Matrix3<T> {
+        let mut m = [[T::ZERO; 3]; 3];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = self.m[i][0] * rhs.m[0][j]
+                    + self.m[i][1] * rhs.m[1][j]
+                    + self.m[i][2] * rhs.m[2][j];
+            }
+        }
+        Matrix3 { m }
+    }
+}
+
+// implement multiplication for mat3 * vec3, treating the vector as a column
+impl<T: Scalar> Mul<Vector3<T>> for Matrix3<T> {
+    type Output = Vector3<T>;
+
+    fn mul(self, rhs: Vector3<T>) -> Vector3<T> {
+        let mut v = [T::ZERO; 3];
+        for (i, row) in self.m.iter().enumerate() {
+            v[i] = row[0] * rhs.x + row[1] * rhs.y + row[2] * rhs.z;
+        }
+        Vector3::new(v[0], v[1], v[2])
+    }
+}
+
+// Matrix 2x2 type
+#[derive(Debug, Copy, Clone)]
+pub struct Matrix2<T> {
+    pub m: [[T; 2]; 2],
+}
 
-    fn mul(self, rhs: Mat4) -> Vec3 {
-        let mut v = [0.0; 4];
+// Matrix 2x2 operations
+impl<T: Scalar> Matrix2<T> {
+    pub fn new(m: [[T; 2]; 2]) -> Matrix2<T> {
+        Matrix2 { m }
+    }
+
+    pub fn identity() -> Matrix2<T> {
+        Matrix2 {
+            m: [[T::ONE, T::ZERO], [T::ZERO, T::ONE]],
+        }
+    }
+
+    pub fn from_cols(x: Vector2<T>, y: Vector2<T>) -> Matrix2<T> {
+        Matrix2 {
+            m: [[x.x, y.x], [x.y, y.y]],
+        }
+    }
+
+    // counter clockwise in a y up space
+    pub fn rotation(angle: T) -> Matrix2<T> {
+        let s = angle.sin();
+        let c = angle.cos();
+        Matrix2 {
+            m: [[c, -s], [s, c]],
+        }
+    }
+
+    pub fn scale(scale: Vector2<T>) -> Matrix2<T> {
+        Matrix2 {
+            m: [[scale.x, T::ZERO], [T::ZERO, scale.y]],
+        }
+    }
+
+    pub fn transpose(&self) -> Matrix2<T> {
+        Matrix2 {
+            m: [[self.m[0][0], self.m[1][0]], [self.m[0][1], self.m[1][1]]],
+        }
+    }
+
+    pub fn determinant(&self) -> T {
+        self.m[0][0] * self.m[1][1] - self.m[0][1] * self.m[1][0]
+    }
+
+    pub fn inverse(&self) -> Option<Matrix2<T>> {
+        let det = self.determinant();
+        if det.abs() < T::from_f64(1e-12) {
+            return None;
+        }
+        let inv_det = T::ONE / det;
+        Some(Matrix2 {
+            m: [
+                [self.m[1][1] * inv_det, -self.m[0][1] * inv_det],
+                [-self.m[1][0] * inv_det, self.m[0][0] * inv_det],
+            ],
+        })
+    }
+
+    // the upper left 2x2
+    pub fn from_mat3(mat: &Matrix3<T>) -> Matrix2<T> {
+        Matrix2 {
+            m: [[mat.m[0][0], mat.m[0][1]], [mat.m[1][0], mat.m[1][1]]],
+        }
+    }
+
+    pub fn to_mat3(&self) -> Matrix3<T> {
+        let mut m = Matrix3::identity().m;
+        for (i, row) in self.m.iter().enumerate() {
+            m[i][..2].copy_from_slice(row);
+        }
+        Matrix3 { m }
+    }
+
+    pub fn from_mat4(mat: &Matrix4<T>) -> Matrix2<T> {
+        Matrix2 {
+            m: [[mat.m[0][0], mat.m[0][1]], [mat.m[1][0], mat.m[1][1]]],
+        }
+    }
+
+    pub fn to_mat4(&self) -> Matrix4<T> {
+        let mut m = Matrix4::identity().m;
+        for (i, row) in self.m.iter().enumerate() {
+            m[i][..2].copy_from_slice(row);
+        }
+        Matrix4 { m }
+    }
+
+    pub fn cast<U: Scalar>(&self) -> Matrix2<U> {
+        Matrix2 {
+            m: self.m.map(|row| row.map(|v| U::from_f64(v.to_f64()))),
+        }
+    }
+}
+
+impl<T: Scalar> Mul for Matrix2<T> {
+    type Output = Matrix2<T>;
+
+    fn mul(self, rhs: Matrix2<T>) -> Matrix2<T> {
+        let mut m = [[T::ZERO; 2]; 2];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = self.m[i][0] * rhs.m[0][j] + self.m[i][1] * rhs.m[1][j];
+            }
+        }
+        Matrix2 { m }
+    }
+}
+
+// implement multiplication for mat2 * vec2, treating the vector as a column
+impl<T: Scalar> Mul<Vector2<T>> for Matrix2<T> {
+    type Output = Vector2<T>;
+
+    fn mul(self, rhs: Vector2<T>) -> Vector2<T> {
+        Vector2::new(
+            self.m[0][0] * rhs.x + self.m[0][1] * rhs.y,
+            self.m[1][0] * rhs.x + self.m[1][1] * rhs.y,
+        )
+    }
+}
+
+// Quaternion type, unit ones are rotations
+#[derive(Debug, Copy, Clone)]
+pub struct Quaternion<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+    pub w: T,
+}
+
+// Quaternion operations
+impl<T: Scalar> Quaternion<T> {
+    pub fn new(x: T, y: T, z: T, w: T) -> Quaternion<T> {
+        Quaternion { x, y, z, w }
+    }
+
+    pub fn identity() -> Quaternion<T> {
+        Quaternion::new(T::ZERO, T::ZERO, T::ZERO, T::ONE)
+    }
+
+    // same handedness as Mat4::rotation
+    pub fn from_axis_angle(axis: Vector3<T>, angle: T) -> Quaternion<T> {
+        let half = angle * T::from_f64(0.5);
+        let a = axis.normalize() * half.sin();
+        Quaternion::new(a.x, a.y, a.z, half.cos())
+    }
+
+    // the same rotation as Mat4::grotation, rx * ry * rz, so z is applied first
+    pub fn from_euler(x: T, y: T, z: T) -> Quaternion<T> {
+        let axis = |x, y, z| Vector3::new(x, y, z);
+        Quaternion::from_axis_angle(axis(T::ONE, T::ZERO, T::ZERO), x)
+            * Quaternion::from_axis_angle(axis(T::ZERO, T::ONE, T::ZERO), y)
+            * Quaternion::from_axis_angle(axis(T::ZERO, T::ZERO, T::ONE), z)
+    }
+
+    // from a pure rotation matrix, picks the largest component to divide by for stability
+    pub fn from_mat3(mat: &Matrix3<T>) -> Quaternion<T> {
+        let m = &mat.m;
+        let quarter = T::from_f64(0.25);
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        if trace > T::ZERO {
+            let s = (trace + T::ONE).sqrt() * T::from_f64(2.0);
+            Quaternion::new(
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+                quarter * s,
+            )
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (T::ONE + m[0][0] - m[1][1] - m[2][2]).sqrt() * T::from_f64(2.0);
+            Quaternion::new(
+                quarter * s,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[2][1] - m[1][2]) / s,
+            )
+        } else if m[1][1] > m[2][2] {
+            let s = (T::ONE + m[1][1] - m[0][0] - m[2][2]).sqrt() * T::from_f64(2.0);
+            Quaternion::new(
+                (m[0][1] + m[1][0]) / s,
+                quarter * s,
+                (m[1][2] + m[2][1]) / s,
+                (m[0][2] - m[2][0]) / s,
+            )
+        } else {
+            let s = (T::ONE + m[2][2] - m[0][0] - m[1][1]).sqrt() * T::from_f64(2.0);
+            Quaternion::new(
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                quarter * s,
+                (m[1][0] - m[0][1]) / s,
+            )
+        }
+    }
+
+    pub fn dot(&self, other: &Quaternion<T>) -> T {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn length(&self) -> T {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(&self) -> Quaternion<T> {
+        let len = self.length();
+        Quaternion::new(self.x / len, self.y / len, self.z / len, self.w / len)
+    }
+
+    pub fn conjugate(&self) -> Quaternion<T> {
+        Quaternion::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    // the conjugate over the squared length, None for a zero quaternion
+    pub fn inverse(&self) -> Option<Quaternion<T>> {
+        let len2 = self.dot(self);
+        if len2 < T::from_f64(1e-12) {
+            return None;
+        }
+        let c = self.conjugate();
+        Some(Quaternion::new(
+            c.x / len2,
+            c.y / len2,
+            c.z / len2,
+            c.w / len2,
+        ))
+    }
+
+    // q v q*, expanded so it skips building the full products
+    pub fn rotate(&self, v: Vector3<T>) -> Vector3<T> {
+        let u = Vector3::new(self.x, self.y, self.z);
+        let t = u.cross(&v) * T::from_f64(2.0);
+        v + t * self.w + u.cross(&t)
+    }
+
+    // shortest path, falls back to a plain lerp when the two are nearly the same
+    pub fn slerp(&self, other: &Quaternion<T>, t: T) -> Quaternion<T> {
+        let mut b = *other;
+        let mut cos = self.dot(other);
+        if cos < T::ZERO {
+            b = Quaternion::new(-b.x, -b.y, -b.z, -b.w);
+            cos = -cos;
+        }
+        let (wa, wb) = if cos > T::from_f64(0.9995) {
+            (T::ONE - t, t)
+        } else {
+            let angle = cos.acos();
+            let sin = angle.sin();
+            (((T::ONE - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+        Quaternion::new(
+            self.x * wa + b.x * wb,
+            self.y * wa + b.y * wb,
+            self.z * wa + b.z * wb,
+            self.w * wa + b.w * wb,
+        )
+        .normalize()
+    }
+
+    pub fn to_mat3(&self) -> Matrix3<T> {
+        let (x, y, z, w) = (self.x, self.y, self.z, self.w);
+        let two = T::from_f64(2.0);
+        Matrix3 {
+            m: [
+                [
+                    T::ONE - two * (y * y + z * z),
+                    two * (x * y - z * w),
+                    two * (x * z + y * w),
+                ],
+                [
+                    two * (x * y + z * w),
+                    T::ONE - two * (x * x + z * z),
+                    two * (y * z - x * w),
+                ],
+                [
+                    two * (x * z - y * w),
+                    two * (y * z + x * w),
+                    T::ONE - two * (x * x + y * y),
+                ],
+            ],
+        }
+    }
+
+    pub fn to_mat4(&self) -> Matrix4<T> {
+        self.to_mat3().to_mat4()
+    }
+
+    pub fn cast<U: Scalar>(&self) -> Quaternion<U> {
+        Quaternion {
+            x: U::from_f64(self.x.to_f64()),
+            y: U::from_f64(self.y.to_f64()),
+            z: U::from_f64(self.z.to_f64()),
+            w: U::from_f64(self.w.to_f64()),
+        }
+    }
+}
+
+// hamilton product, a * b rotates by b first and then a
+impl<T: Scalar> Mul for Quaternion<T> {
+    type Output = Quaternion<T>;
+
+    fn mul(self, b: Quaternion<T>) -> Quaternion<T> {
+        let a = self;
+        Quaternion {
+            x: a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            y: a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            z: a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+            w: a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+        }
+    }
+}
+
+/*
+    geometric primitives
+    rays, planes, boxes, spheres, triangles and frustums, with the intersection
+    and closest point queries culling and picking need
+    generic like the vectors, with the same short f32 and d prefixed f64 names
+*/
+
+pub type Ray = Ray3<f32>;
+pub type Plane = Plane3<f32>;
+pub type Aabb = Aabb3<f32>;
+pub type Sphere = Sphere3<f32>;
+pub type Triangle = Triangle3<f32>;
+pub type Frustum = Frustum3<f32>;
+
+pub type DRay = Ray3<f64>;
+pub type DPlane = Plane3<f64>;
+pub type DAabb = Aabb3<f64>;
+pub type DSphere = Sphere3<f64>;
+pub type DTriangle = Triangle3<f64>;
+pub type DFrustum = Frustum3<f64>;
+
+// origin plus a unit direction, t along it is distance
+#[derive(Debug, Copy, Clone)]
+pub struct Ray3<T> {
+    pub origin: Vector3<T>,
+    pub direction: Vector3<T>,
+}
+
+impl<T: Scalar> Ray3<T> {
+    pub fn new(origin: Vector3<T>, direction: Vector3<T>) -> Ray3<T> {
+        Ray3 {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    pub fn at(&self, t: T) -> Vector3<T> {
+        self.origin + self.direction * t
+    }
+
+    // closest point on the ray, never behind the origin
+    pub fn closest_point(&self, p: Vector3<T>) -> Vector3<T> {
+        let t = (p - self.origin).dot(&self.direction).max(T::ZERO);
+        self.at(t)
+    }
+
+    pub fn intersect_plane(&self, plane: &Plane3<T>) -> Option<T> {
+        let denom = plane.normal.dot(&self.direction);
+        if denom.abs() < T::from_f64(1e-12) {
+            return None;
+        }
+        let t = -plane.distance(self.origin) / denom;
+        if t < T::ZERO {
+            return None;
+        }
+        Some(t)
+    }
+
+    // distance to the first hit, zero if the ray starts inside
+    pub fn intersect_sphere(&self, sphere: &Sphere3<T>) -> Option<T> {
+        let oc = self.origin - sphere.center;
+        let b = oc.dot(&self.direction);
+        let c = oc.dot(&oc) - sphere.radius * sphere.radius;
+        // outside and pointing away
+        if c > T::ZERO && b > T::ZERO {
+            return None;
+        }
+        let disc = b * b - c;
+        if disc < T::ZERO {
+            return None;
+        }
+        Some((-b - disc.sqrt()).max(T::ZERO))
+    }
+
+    // slab test, zero if the ray starts inside
+    pub fn intersect_aabb(&self, aabb: &Aabb3<T>) -> Option<T> {
+        let mut t_min = T::ZERO;
+        let mut t_max = T::INFINITY;
+        for a in 0..3 {
+            // dividing by a zero component gives infinities that keep the slab test working
+            let inv = T::ONE / self.direction[a];
+            let mut t0 = (aabb.min[a] - self.origin[a]) * inv;
+            let mut t1 = (aabb.max[a] - self.origin[a]) * inv;
+            if inv < T::ZERO {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some(t_min)
+    }
+
+    // moller-trumbore, hits both sides
+    // returns the distance and the barycentric weights of b and c
+    pub fn intersect_triangle(&self, tri: &Triangle3<T>) -> Option<(T, T, T)> {
+        let e1 = tri.b - tri.a;
+        let e2 = tri.c - tri.a;
+        let p = self.direction.cross(&e2);
+        let det = e1.dot(&p);
+        if det.abs() < T::from_f64(1e-12) {
+            return None;
+        }
+        let inv_det = T::ONE / det;
+        let s = self.origin - tri.a;
+        let u = s.dot(&p) * inv_det;
+        if u < T::ZERO || u > T::ONE {
+            return None;
+        }
+        let q = s.cross(&e1);
+        let v = self.direction.dot(&q) * inv_det;
+        if v < T::ZERO || u + v > T::ONE {
+            return None;
+        }
+        let t = e2.dot(&q) * inv_det;
+        if t < T::ZERO {
+            return None;
+        }
+        Some((t, u, v))
+    }
+}
+
+// points with normal . p + d = 0, the normal side is in front
+#[derive(Debug, Copy, Clone)]
+pub struct Plane3<T> {
+    pub normal: Vector3<T>,
+    pub d: T,
+}
+
+impl<T: Scalar> Plane3<T> {
+    pub fn new(normal: Vector3<T>, d: T) -> Plane3<T> {
+        Plane3 { normal, d }
+    }
+
+    pub fn from_point_normal(point: Vector3<T>, normal: Vector3<T>) -> Plane3<T> {
+        let normal = normal.normalize();
+        Plane3 {
+            normal,
+            d: -normal.dot(&point),
+        }
+    }
+
+    // counter clockwise points face the front
+    pub fn from_points(a: Vector3<T>, b: Vector3<T>, c: Vector3<T>) -> Plane3<T> {
+        Plane3::from_point_normal(a, (b - a).cross(&(c - a)))
+    }
+
+    // scales the equation so the normal is unit length and distances come out right
+    pub fn normalize(&self) -> Plane3<T> {
+        let len = self.normal.length();
+        Plane3 {
+            normal: self.normal / len,
+            d: self.d / len,
+        }
+    }
+
+    // signed, positive in front
+    pub fn distance(&self, p: Vector3<T>) -> T {
+        self.normal.dot(&p) + self.d
+    }
+
+    pub fn closest_point(&self, p: Vector3<T>) -> Vector3<T> {
+        p - self.normal * self.distance(p)
+    }
+}
+
+// axis aligned box
+#[derive(Debug, Copy, Clone)]
+pub struct Aabb3<T> {
+    pub min: Vector3<T>,
+    pub max: Vector3<T>,
+}
+
+impl<T: Scalar> Aabb3<T> {
+    pub fn new(min: Vector3<T>, max: Vector3<T>) -> Aabb3<T> {
+        Aabb3 { min, max }
+    }
+
+    // None for an empty list
+    pub fn from_points(points: &[Vector3<T>]) -> Option<Aabb3<T>> {
+        let first = *points.first()?;
+        Some(
+            points
+                .iter()
+                .fold(Aabb3::new(first, first), |aabb, p| Aabb3 {
+                    min: aabb.min.min(p),
+                    max: aabb.max.max(p),
+                }),
+        )
+    }
+
+    pub fn center(&self) -> Vector3<T> {
+        (self.min + self.max) * T::from_f64(0.5)
+    }
+
+    // half the size on each axis
+    pub fn extents(&self) -> Vector3<T> {
+        (self.max - self.min) * T::from_f64(0.5)
+    }
+
+    pub fn contains(&self, p: Vector3<T>) -> bool {
+        (0..3).all(|a| p[a] >= self.min[a] && p[a] <= self.max[a])
+    }
+
+    pub fn intersects(&self, other: &Aabb3<T>) -> bool {
+        (0..3).all(|a| self.min[a] <= other.max[a] && self.max[a] >= other.min[a])
+    }
+
+    pub fn closest_point(&self, p: Vector3<T>) -> Vector3<T> {
+        p.max(&self.min).min(&self.max)
+    }
+
+    // box around the transformed box, arvo's method, no need to transform all eight corners
+    pub fn transform(&self, m: &Matrix4<T>) -> Aabb3<T> {
+        let mut min = Vector3::new(m.m[0][3], m.m[1][3], m.m[2][3]);
+        let mut max = min;
+        for i in 0..3 {
+            for j in 0..3 {
+                let a = m.m[i][j] * self.min[j];
+                let b = m.m[i][j] * self.max[j];
+                min[i] += a.min(b);
+                max[i] += a.max(b);
+            }
+        }
+        Aabb3 { min, max }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Sphere3<T> {
+    pub center: Vector3<T>,
+    pub radius: T,
+}
+
+impl<T: Scalar> Sphere3<T> {
+    pub fn new(center: Vector3<T>, radius: T) -> Sphere3<T> {
+        Sphere3 { center, radius }
+    }
+
+    // not the tightest sphere, centered on the bounding box
+    pub fn from_points(points: &[Vector3<T>]) -> Option<Sphere3<T>> {
+        let center = Aabb3::from_points(points)?.center();
+        let radius = points
+            .iter()
+            .map(|p| (*p - center).length())
+            .fold(T::ZERO, |a, b| a.max(b));
+        Some(Sphere3 { center, radius })
+    }
+
+    pub fn contains(&self, p: Vector3<T>) -> bool {
+        let d = p - self.center;
+        d.dot(&d) <= self.radius * self.radius
+    }
+
+    pub fn intersects(&self, other: &Sphere3<T>) -> bool {
+        let d = other.center - self.center;
+        let r = self.radius + other.radius;
+        d.dot(&d) <= r * r
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb3<T>) -> bool {
+        self.contains(aabb.closest_point(self.center))
+    }
+
+    // on the surface, or the point itself if it is inside
+    pub fn closest_point(&self, p: Vector3<T>) -> Vector3<T> {
+        let d = p - self.center;
+        let len = d.length();
+        if len <= self.radius {
+            return p;
+        }
+        self.center + d * (self.radius / len)
+    }
+
+    // the biggest axis scale keeps it around everything the transformed points could reach
+    pub fn transform(&self, m: &Matrix4<T>) -> Sphere3<T> {
+        let center = (*m * self.center.extend(T::ONE)).xyz();
+        let scale = (0..3)
+            .map(|col| Vector3::new(m.m[0][col], m.m[1][col], m.m[2][col]).length())
+            .fold(T::ZERO, |a, b| a.max(b));
+        Sphere3 {
+            center,
+            radius: self.radius * scale,
+        }
+    }
+}
+
+// counter clockwise seen from the front
+#[derive(Debug, Copy, Clone)]
+pub struct Triangle3<T> {
+    pub a: Vector3<T>,
+    pub b: Vector3<T>,
+    pub c: Vector3<T>,
+}
+
+impl<T: Scalar> Triangle3<T> {
+    pub fn new(a: Vector3<T>, b: Vector3<T>, c: Vector3<T>) -> Triangle3<T> {
+        Triangle3 { a, b, c }
+    }
+
+    pub fn normal(&self) -> Vector3<T> {
+        (self.b - self.a).cross(&(self.c - self.a)).normalize()
+    }
+
+    pub fn plane(&self) -> Plane3<T> {
+        Plane3::from_points(self.a, self.b, self.c)
+    }
+
+    // weights of a, b and c for a point on the triangle's plane
+    pub fn barycentric(&self, p: Vector3<T>) -> Vector3<T> {
+        let (v0, v1, v2) = (self.b - self.a, self.c - self.a, p - self.a);
+        let (d00, d01, d11) = (v0.dot(&v0), v0.dot(&v1), v1.dot(&v1));
+        let (d20, d21) = (v2.dot(&v0), v2.dot(&v1));
+        let denom = d00 * d11 - d01 * d01;
+        let v = (d11 * d20 - d01 * d21) / denom;
+        let w = (d00 * d21 - d01 * d20) / denom;
+        Vector3::new(T::ONE - v - w, v, w)
+    }
+
+    // walks the voronoi regions of the corners and edges, from ericson's real-time collision detection
+    pub fn closest_point(&self, p: Vector3<T>) -> Vector3<T> {
+        let (a, b, c) = (self.a, self.b, self.c);
+        let ab = b - a;
+        let ac = c - a;
+        let ap = p - a;
+        let d1 = ab.dot(&ap);
+        let d2 = ac.dot(&ap);
+        if d1 <= T::ZERO && d2 <= T::ZERO {
+            return a;
+        }
+        let bp = p - b;
+        let d3 = ab.dot(&bp);
+        let d4 = ac.dot(&bp);
+        if d3 >= T::ZERO && d4 <= d3 {
+            return b;
+        }
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= T::ZERO && d1 >= T::ZERO && d3 <= T::ZERO {
+            return a + ab * (d1 / (d1 - d3));
+        }
+        let cp = p - c;
+        let d5 = ab.dot(&cp);
+        let d6 = ac.dot(&cp);
+        if d6 >= T::ZERO && d5 <= d6 {
+            return c;
+        }
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= T::ZERO && d2 >= T::ZERO && d6 <= T::ZERO {
+            return a + ac * (d2 / (d2 - d6));
+        }
+        let va = d3 * d6 - d5 * d4;
+        if va <= T::ZERO && d4 - d3 >= T::ZERO && d5 - d6 >= T::ZERO {
+            return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+        }
+        let denom = T::ONE / (va + vb + vc);
+        a + ab * (vb * denom) + ac * (vc * denom)
+    }
+}
+
+// six planes facing inwards, left right bottom top near far
+#[derive(Debug, Copy, Clone)]
+pub struct Frustum3<T> {
+    pub planes: [Plane3<T>; 6],
+}
+
+impl<T: Scalar> Frustum3<T> {
+    pub fn new(planes: [Plane3<T>; 6]) -> Frustum3<T> {
+        Frustum3 { planes }
+    }
+
+    // gribb and hartmann, the planes fall out of sums of the matrix rows.
+    // with clip = m * p a point is inside when -w <= x, y, z <= w, so for the
+    // left plane 0 <= w + x = (row3 + row0) . p and likewise for the rest.
+    // in world space for a view projection, in object space for a full mvp
+    pub fn from_matrix(m: &Matrix4<T>) -> Frustum3<T> {
+        let row = |i: usize| Vector4::new(m.m[i][0], m.m[i][1], m.m[i][2], m.m[i][3]);
+        let plane = |v: Vector4<T>| Plane3::new(v.xyz(), v.w).normalize();
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+        Frustum3 {
+            planes: [
+                plane(r3 + r0),
+                plane(r3 - r0),
+                plane(r3 + r1),
+                plane(r3 - r1),
+                plane(r3 + r2),
+                plane(r3 - r2),
+            ],
+        }
+    }
+
+    pub fn contains(&self, p: Vector3<T>) -> bool {
+        self.planes.iter().all(|plane| plane.distance(p) >= T::ZERO)
+    }
+
+    // conservative, spheres near the corners can pass without touching
+    pub fn intersects_sphere(&self, sphere: &Sphere3<T>) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.distance(sphere.center) >= -sphere.radius)
+    }
+
+    // conservative too, tests the corner furthest along each plane's normal
+    pub fn intersects_aabb(&self, aabb: &Aabb3<T>) -> bool {
+        self.planes.iter().all(|plane| {
+            let mut p = aabb.min;
+            for a in 0..3 {
+                if plane.normal[a] >= T::ZERO {
+                    p[a] = aabb.max[a];
+                }
+            }
+            plane.distance(p) >= T::ZERO
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    fn close_vec(a: Vec3, b: Vec3) -> bool {
+        close(a.x, b.x) && close(a.y, b.y) && close(a.z, b.z)
+    }
+
+    fn down_z() -> Ray {
+        Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0))
+    }
+
+    #[test]
+    fn ray_plane() {
+        let plane = Plane::from_point_normal(Vec3::zero(), Vec3::new(0.0, 0.0, 1.0));
+        assert!(close(down_z().intersect_plane(&plane).unwrap(), 5.0));
+        let away = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(away.intersect_plane(&plane).is_none());
+        let parallel = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(parallel.intersect_plane(&plane).is_none());
+    }
+
+    #[test]
+    fn ray_sphere() {
+        let sphere = Sphere::new(Vec3::zero(), 1.0);
+        assert!(close(down_z().intersect_sphere(&sphere).unwrap(), 4.0));
+        let inside = Ray::new(Vec3::zero(), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(inside.intersect_sphere(&sphere), Some(0.0));
+        let miss = Ray::new(Vec3::new(2.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(miss.intersect_sphere(&sphere).is_none());
+        let behind = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(behind.intersect_sphere(&sphere).is_none());
+    }
+
+    #[test]
+    fn ray_aabb() {
+        let aabb = Aabb::new(Vec3::one() * -1.0, Vec3::one());
+        assert!(close(down_z().intersect_aabb(&aabb).unwrap(), 4.0));
+        // axis aligned rays divide by zero on the other axes
+        let grazing = Ray::new(Vec3::new(1.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(grazing.intersect_aabb(&aabb).is_some());
+        let miss = Ray::new(Vec3::new(1.5, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(miss.intersect_aabb(&aabb).is_none());
+        let inside = Ray::new(Vec3::zero(), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(inside.intersect_aabb(&aabb), Some(0.0));
+    }
+
+    #[test]
+    fn ray_triangle() {
+        let tri = Triangle::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+        let ray = Ray::new(Vec3::new(0.25, 0.5, 3.0), Vec3::new(0.0, 0.0, -1.0));
+        let (t, u, v) = ray.intersect_triangle(&tri).unwrap();
+        assert!(close(t, 3.0) && close(u, 0.25) && close(v, 0.5));
+        // both sides hit
+        let back = Ray::new(Vec3::new(0.25, 0.25, -3.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(back.intersect_triangle(&tri).is_some());
+        let outside = Ray::new(Vec3::new(0.75, 0.75, 3.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(outside.intersect_triangle(&tri).is_none());
+        let weights = tri.barycentric(Vec3::new(0.25, 0.5, 0.0));
+        assert!(close_vec(weights, Vec3::new(0.25, 0.25, 0.5)));
+    }
+
+    #[test]
+    fn aabb_and_sphere_queries() {
+        let aabb = Aabb::new(Vec3::zero(), Vec3::one());
+        assert!(aabb.contains(Vec3::one() * 0.5));
+        assert!(!aabb.contains(Vec3::one() * 1.5));
+        assert!(close_vec(
+            aabb.closest_point(Vec3::new(2.0, 0.5, -1.0)),
+            Vec3::new(1.0, 0.5, 0.0)
+        ));
+        let moved = aabb.transform(&Mat4::translation(Vec3::new(2.0, 0.0, 0.0)));
+        assert!(close_vec(moved.min, Vec3::new(2.0, 0.0, 0.0)));
+        assert!(!aabb.intersects(&moved));
+
+        let sphere = Sphere::new(Vec3::zero(), 1.0);
+        assert!(sphere.intersects(&Sphere::new(Vec3::new(1.5, 0.0, 0.0), 0.6)));
+        assert!(!sphere.intersects(&Sphere::new(Vec3::new(3.0, 0.0, 0.0), 0.6)));
+        assert!(sphere.intersects_aabb(&Aabb::new(Vec3::one() * 0.5, Vec3::one())));
+        let scaled = sphere.transform(&Mat4::scale(Vec3::new(1.0, 3.0, 2.0)));
+        assert!(close(scaled.radius, 3.0));
+    }
+
+    #[test]
+    fn frustum_culling() {
+        let proj = Mat4::perspective(1.0, 1.0, 0.1, 100.0);
+        let view = Mat4::look_at(
+            Vec3::zero(),
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+        let frustum = Frustum::from_matrix(&(proj * view));
+        assert!(frustum.contains(Vec3::new(0.0, 0.0, -5.0)));
+        assert!(!frustum.contains(Vec3::new(0.0, 0.0, 5.0)));
+        assert!(!frustum.contains(Vec3::new(0.0, 0.0, -200.0)));
+        assert!(!frustum.contains(Vec3::new(50.0, 0.0, -5.0)));
+        assert!(frustum.intersects_sphere(&Sphere::new(Vec3::new(0.0, 0.0, 0.5), 1.0)));
+        assert!(!frustum.intersects_sphere(&Sphere::new(Vec3::new(0.0, 0.0, 5.0), 1.0)));
+        assert!(frustum.intersects_aabb(&Aabb::new(
+            Vec3::new(-1.0, -1.0, -6.0),
+            Vec3::new(1.0, 1.0, -4.0)
+        )));
+        assert!(!frustum.intersects_aabb(&Aabb::new(
+            Vec3::new(-1.0, -1.0, 4.0),
+            Vec3::new(1.0, 1.0, 6.0)
+        )));
+    }
+
+    #[test]
+    fn mat4_inverse() {
+        let m = Mat4::translation(Vec3::new(1.0, 2.0, 3.0))
+            * Mat4::rotation(0.7, Vec3::new(1.0, 1.0, 0.0).normalize())
+            * Mat4::scale(Vec3::new(2.0, 0.5, 3.0));
+        let product = m * m.inverse().unwrap();
+        let identity = Mat4::identity();
         for i in 0..4 {
-            v[i] = self.x * rhs.m[0][i] + self.y * rhs.m[1][i] + self.z * rhs.m[2][i] + rhs.m[3][i];
+            for j in 0..4 {
+                assert!(close(product.m[i][j], identity.m[i][j]));
+            }
         }
-        Vec3::new(v[0], v[1], v[2])
+        assert!(Mat4::scale(Vec3::new(1.0, 0.0, 1.0)).inverse().is_none());
+    }
+
+    #[test]
+    fn euler_matches_grotation() {
+        let (x, y, z) = (0.3, -1.1, 2.0);
+        let from_quat = Quat::from_euler(x, y, z).to_mat4();
+        let from_mat = Mat4::grotation(x, y, z);
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!(close(from_quat.m[i][j], from_mat.m[i][j]));
+            }
+        }
+    }
+
+    #[test]
+    fn quaternions() {
+        let axis = Vec3::new(0.0, 1.0, 0.0);
+        let q = Quat::from_axis_angle(axis, std::f32::consts::FRAC_PI_2);
+        let v = Vec3::new(1.0, 0.0, 0.0);
+        assert!(close_vec(q.rotate(v), Vec3::new(0.0, 0.0, -1.0)));
+        assert!(close_vec((q.to_mat4() * v.extend(1.0)).xyz(), q.rotate(v)));
+        assert!(close_vec(q.conjugate().rotate(q.rotate(v)), v));
+        let back = Quat::from_mat3(&q.to_mat3());
+        assert!(close(back.dot(&q).abs(), 1.0));
+        let half = Quat::identity().slerp(&q, 0.5);
+        let quarter = Quat::from_axis_angle(axis, std::f32::consts::FRAC_PI_4);
+        assert!(close(half.dot(&quarter).abs(), 1.0));
     }
 }
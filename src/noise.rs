@@ -0,0 +1,167 @@
+/*
+    noise
+    a small seeded rng and threshold maps for dithering
+    all threshold maps are row major with values in [0, 1)
+*/
+
+// xorshift64*, plenty for sample patterns and dither masks
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed.max(1) }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        (self.state.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32) as u32
+    }
+
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    pub fn range(&mut self, n: usize) -> usize {
+        self.next_u32() as usize % n
+    }
+}
+
+// classic recursive bayer matrix, n must be a power of two
+pub fn bayer_matrix(n: usize) -> Vec<f32> {
+    assert!(
+        n.is_power_of_two(),
+        "bayer matrix size must be a power of two"
+    );
+    let mut m = vec![0usize];
+    let mut size = 1;
+    while size < n {
+        let next_size = size * 2;
+        let mut next = vec![0; next_size * next_size];
+        for y in 0..size {
+            for x in 0..size {
+                let v = m[y * size + x] * 4;
+                next[y * next_size + x] = v;
+                next[y * next_size + x + size] = v + 2;
+                next[(y + size) * next_size + x] = v + 3;
+                next[(y + size) * next_size + x + size] = v + 1;
+            }
+        }
+        m = next;
+        size = next_size;
+    }
+    let cells = (n * n) as f32;
+    m.iter().map(|&v| (v as f32 + 0.5) / cells).collect()
+}
+
+// energy of every pixel from the set ones, gaussian falloff that wraps around
+#[derive(Clone)]
+struct Energy {
+    size: usize,
+    kernel: Vec<f32>,
+    values: Vec<f32>,
+}
+
+impl Energy {
+    fn new(size: usize, sigma: f32) -> Energy {
+        let mut kernel = vec![0.0; size * size];
+        for dy in 0..size {
+            for dx in 0..size {
+                let wx = dx.min(size - dx) as f32;
+                let wy = dy.min(size - dy) as f32;
+                kernel[dy * size + dx] = (-(wx * wx + wy * wy) / (2.0 * sigma * sigma)).exp();
+            }
+        }
+        Energy {
+            size,
+            kernel,
+            values: vec![0.0; size * size],
+        }
+    }
+
+    fn update(&mut self, index: usize, sign: f32) {
+        let (px, py) = (index % self.size, index / self.size);
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let dx = (x + self.size - px) % self.size;
+                let dy = (y + self.size - py) % self.size;
+                self.values[y * self.size + x] += sign * self.kernel[dy * self.size + dx];
+            }
+        }
+    }
+
+    // tightest cluster among set pixels, or largest void among unset ones
+    fn extreme(&self, pattern: &[bool], set: bool) -> usize {
+        let mut best = usize::MAX;
+        for (i, &p) in pattern.iter().enumerate() {
+            if p != set {
+                continue;
+            }
+            let better = best == usize::MAX
+                || (set && self.values[i] > self.values[best])
+                || (!set && self.values[i] < self.values[best]);
+            if better {
+                best = i;
+            }
+        }
+        best
+    }
+}
+
+// void and cluster blue noise (ulichney 1993), size * size thresholds
+pub fn blue_noise(size: usize, seed: u64) -> Vec<f32> {
+    let n = size * size;
+    let mut rng = Rng::new(seed);
+
+    // random starting pattern with about a tenth of the pixels set
+    let mut pattern = vec![false; n];
+    let mut energy = Energy::new(size, 1.5);
+    let initial = (n / 10).max(1);
+    let mut count = 0;
+    while count < initial {
+        let i = rng.range(n);
+        if !pattern[i] {
+            pattern[i] = true;
+            energy.update(i, 1.0);
+            count += 1;
+        }
+    }
+
+    // move the tightest clusters into the largest voids until it settles
+    loop {
+        let cluster = energy.extreme(&pattern, true);
+        pattern[cluster] = false;
+        energy.update(cluster, -1.0);
+        let void = energy.extreme(&pattern, false);
+        pattern[void] = true;
+        energy.update(void, 1.0);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut rank = vec![0usize; n];
+
+    // phase 1, take the starting pattern apart cluster by cluster
+    let mut p = pattern.clone();
+    let mut e = energy.clone();
+    for r in (0..initial).rev() {
+        let cluster = e.extreme(&p, true);
+        p[cluster] = false;
+        e.update(cluster, -1.0);
+        rank[cluster] = r;
+    }
+
+    // phase 2 and 3, fill the largest voids until every pixel has a rank
+    for r in initial..n {
+        let void = energy.extreme(&pattern, false);
+        pattern[void] = true;
+        energy.update(void, 1.0);
+        rank[void] = r;
+    }
+
+    rank.iter().map(|&r| (r as f32 + 0.5) / n as f32).collect()
+}
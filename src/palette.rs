@@ -0,0 +1,134 @@
+/*
+    palettes
+    fixed color sets for retro output modes, the built in console palettes
+    and user palettes loaded from .hex or gimp .gpl files
+*/
+
+use crate::mm::Vec3;
+
+#[derive(Debug, Clone)]
+pub struct Palette {
+    pub name: String,
+    pub colors: Vec<Vec3>,
+}
+
+fn rgb(hex: u32) -> Vec3 {
+    Vec3::new(
+        ((hex >> 16) & 0xff) as f32 / 255.0,
+        ((hex >> 8) & 0xff) as f32 / 255.0,
+        (hex & 0xff) as f32 / 255.0,
+    )
+}
+
+// parse one line of a palette file, None for headers, names and comments
+fn parse_line(line: &str) -> Option<Vec3> {
+    let hex = line.trim_start_matches('#');
+    if hex.len() == 6 {
+        if let Ok(v) = u32::from_str_radix(hex, 16) {
+            return Some(rgb(v));
+        }
+    }
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if parts.len() >= 3 {
+        let r = parts[0].parse::<u8>().ok()?;
+        let g = parts[1].parse::<u8>().ok()?;
+        let b = parts[2].parse::<u8>().ok()?;
+        return Some(rgb(((r as u32) << 16) | ((g as u32) << 8) | b as u32));
+    }
+    None
+}
+
+impl Palette {
+    pub fn new(name: &str, colors: Vec<Vec3>) -> Result<Palette, String> {
+        if colors.is_empty() {
+            return Err(format!("{}: no colors found", name));
+        }
+        Ok(Palette {
+            name: name.to_string(),
+            colors,
+        })
+    }
+
+    fn from_hex(name: &str, hex: &[u32]) -> Palette {
+        Palette {
+            name: name.to_string(),
+            colors: hex.iter().map(|&h| rgb(h)).collect(),
+        }
+    }
+
+    // the original game boy's four shades of green
+    pub fn dmg() -> Palette {
+        Palette::from_hex("dmg", &[0x0f380f, 0x306230, 0x8bac0f, 0x9bbc0f])
+    }
+
+    // cga mode 4, palette 1 high intensity
+    pub fn cga() -> Palette {
+        Palette::from_hex("cga", &[0x000000, 0x55ffff, 0xff55ff, 0xffffff])
+    }
+
+    pub fn ega() -> Palette {
+        Palette::from_hex(
+            "ega",
+            &[
+                0x000000, 0x0000aa, 0x00aa00, 0x00aaaa, 0xaa0000, 0xaa00aa, 0xaa5500, 0xaaaaaa,
+                0x555555, 0x5555ff, 0x55ff55, 0x55ffff, 0xff5555, 0xff55ff, 0xffff55, 0xffffff,
+            ],
+        )
+    }
+
+    pub fn pico8() -> Palette {
+        Palette::from_hex(
+            "pico-8",
+            &[
+                0x000000, 0x1d2b53, 0x7e2553, 0x008751, 0xab5236, 0x5f574f, 0xc2c3c7, 0xfff1e8,
+                0xff004d, 0xffa300, 0xffec27, 0x00e436, 0x29adff, 0x83769c, 0xff77a8, 0xffccaa,
+            ],
+        )
+    }
+
+    // one color per line, either rrggbb hex or "r g b" like gimp palettes
+    pub fn parse(name: &str, text: &str) -> Result<Palette, String> {
+        let colors: Vec<Vec3> = text.lines().filter_map(|l| parse_line(l.trim())).collect();
+        Palette::new(name, colors)
+    }
+
+    pub fn load(path: &str) -> Result<Palette, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Palette::parse(path, &text)
+    }
+
+    // closest color, weighted towards green like the eye is
+    // colors is public, so one emptied after new leaves c as it is
+    pub fn nearest(&self, c: Vec3) -> Vec3 {
+        let mut best = c;
+        let mut best_distance = f32::MAX;
+        for &p in &self.colors {
+            let d = p - c;
+            let distance = 0.3 * d.x * d.x + 0.59 * d.y * d.y + 0.11 * d.z * d.z;
+            if distance < best_distance {
+                best = p;
+                best_distance = distance;
+            }
+        }
+        best
+    }
+
+    // average gap between each color and its closest neighbor
+    // a good default for how far ordered dithering should push colors
+    pub fn spacing(&self) -> f32 {
+        if self.colors.len() < 2 {
+            return 0.0;
+        }
+        let mut total = 0.0;
+        for (i, a) in self.colors.iter().enumerate() {
+            let mut closest = f32::MAX;
+            for (j, b) in self.colors.iter().enumerate() {
+                if i != j {
+                    closest = closest.min((*a - *b).length());
+                }
+            }
+            total += closest;
+        }
+        total / self.colors.len() as f32
+    }
+}
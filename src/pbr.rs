@@ -0,0 +1,444 @@
+/*
+    physically based shading
+    cook-torrance with a ggx distribution, smith geometry and schlick fresnel over
+    gltf style metallic/roughness materials, plus image based lighting from an
+    equirectangular environment with prefiltered specular and irradiance maps
+*/
+
+use crate::light::Light;
+use crate::mm::{Vec2, Vec3, Vec4};
+use crate::texture::{Filter, Texture};
+use std::f32::consts::PI;
+
+// dielectrics all reflect about 4% head on
+const DIELECTRIC_F0: f32 = 0.04;
+// ggx falls apart on perfectly smooth surfaces
+const MIN_ROUGHNESS: f32 = 0.045;
+const SPECULAR_LEVELS: usize = 5;
+const SPECULAR_SAMPLES: u32 = 32;
+const IRRADIANCE_WIDTH: usize = 32;
+const BRDF_LUT_SIZE: usize = 32;
+const BRDF_SAMPLES: u32 = 64;
+
+pub struct PbrMaterial {
+    pub base_color: Vec3,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub emissive: Vec3,
+    pub normal_scale: f32,
+    pub occlusion_strength: f32,
+    // maps multiply the factors above, laid out like gltf:
+    // roughness in green and metallic in blue, occlusion in red
+    pub base_color_map: Option<Texture>,
+    pub metallic_roughness_map: Option<Texture>,
+    pub normal_map: Option<Texture>,
+    pub occlusion_map: Option<Texture>,
+    pub emissive_map: Option<Texture>,
+}
+
+// a material evaluated at one point on a surface
+#[derive(Debug, Copy, Clone)]
+pub struct PbrSurface {
+    pub base_color: Vec3,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub normal: Vec3,
+    pub occlusion: f32,
+    pub emissive: Vec3,
+}
+
+impl PbrMaterial {
+    pub fn new(base_color: Vec3, metallic: f32, roughness: f32) -> PbrMaterial {
+        PbrMaterial {
+            base_color,
+            metallic,
+            roughness,
+            emissive: Vec3::zero(),
+            normal_scale: 1.0,
+            occlusion_strength: 1.0,
+            base_color_map: None,
+            metallic_roughness_map: None,
+            normal_map: None,
+            occlusion_map: None,
+            emissive_map: None,
+        }
+    }
+
+    // tangent is xyz plus handedness in w, a zero tangent skips the normal map
+    pub fn sample(&self, uv: Vec2, filter: Filter, normal: Vec3, tangent: Vec4) -> PbrSurface {
+        let mut base_color = self.base_color;
+        if let Some(map) = &self.base_color_map {
+            base_color *= map.sample(uv, filter);
+        }
+        let (mut metallic, mut roughness) = (self.metallic, self.roughness);
+        if let Some(map) = &self.metallic_roughness_map {
+            let texel = map.sample(uv, filter);
+            roughness *= texel.y;
+            metallic *= texel.z;
+        }
+        let mut occlusion = 1.0;
+        if let Some(map) = &self.occlusion_map {
+            occlusion += (map.sample(uv, filter).x - 1.0) * self.occlusion_strength;
+        }
+        let mut emissive = self.emissive;
+        if let Some(map) = &self.emissive_map {
+            emissive *= map.sample(uv, filter);
+        }
+        let n = match &self.normal_map {
+            Some(map) => map.sample_normal(uv, filter, normal, tangent, self.normal_scale),
+            None => normal.normalize(),
+        };
+        PbrSurface {
+            base_color,
+            metallic: metallic.clamp(0.0, 1.0),
+            roughness: roughness.clamp(MIN_ROUGHNESS, 1.0),
+            normal: n,
+            occlusion,
+            emissive,
+        }
+    }
+}
+
+// normal distribution, how many microfacets face along h
+pub fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+    let a = roughness * roughness;
+    let a2 = a * a;
+    let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    a2 / (PI * d * d)
+}
+
+// smith masking and shadowing with the schlick-ggx approximation
+// k is (r + 1)^2 / 8 for direct lights and r^2 / 2 for image based lighting
+pub fn geometry_smith(n_dot_v: f32, n_dot_l: f32, k: f32) -> f32 {
+    let g1 = |x: f32| x / (x * (1.0 - k) + k);
+    g1(n_dot_v) * g1(n_dot_l)
+}
+
+pub fn fresnel_schlick(cos_theta: f32, f0: Vec3) -> Vec3 {
+    f0 + (Vec3::one() - f0) * (1.0 - cos_theta).clamp(0.0, 1.0).powi(5)
+}
+
+// rough surfaces get less of a bright rim, for ambient light with no single h
+pub fn fresnel_schlick_roughness(cos_theta: f32, f0: Vec3, roughness: f32) -> Vec3 {
+    let r = 1.0 - roughness;
+    let grazing = Vec3::new(r.max(f0.x), r.max(f0.y), r.max(f0.z));
+    f0 + (grazing - f0) * (1.0 - cos_theta).clamp(0.0, 1.0).powi(5)
+}
+
+fn f0(s: &PbrSurface) -> Vec3 {
+    (Vec3::one() * DIELECTRIC_F0).lerp(&s.base_color, s.metallic)
+}
+
+// light reflected towards v from one light arriving along l
+pub fn direct(s: &PbrSurface, v: Vec3, l: Vec3, radiance: Vec3) -> Vec3 {
+    let n = s.normal;
+    let n_dot_l = n.dot(&l);
+    if n_dot_l <= 0.0 {
+        return Vec3::zero();
+    }
+    let n_dot_v = n.dot(&v).max(1e-4);
+    let h = (v + l).normalize();
+    let d = distribution_ggx(n.dot(&h).max(0.0), s.roughness);
+    let k = (s.roughness + 1.0) * (s.roughness + 1.0) / 8.0;
+    let g = geometry_smith(n_dot_v, n_dot_l, k);
+    let f = fresnel_schlick(h.dot(&v).max(0.0), f0(s));
+    let specular = f * (d * g / (4.0 * n_dot_v * n_dot_l));
+    let kd = (Vec3::one() - f) * (1.0 - s.metallic);
+    (kd * s.base_color / PI + specular) * radiance * n_dot_l
+}
+
+// split sum image based lighting, diffuse from the irradiance map and
+// specular from the prefiltered map scaled by the brdf lookup
+pub fn ambient(s: &PbrSurface, v: Vec3, env: &Environment) -> Vec3 {
+    let n = s.normal;
+    let n_dot_v = n.dot(&v).max(1e-4);
+    let f = fresnel_schlick_roughness(n_dot_v, f0(s), s.roughness);
+    let kd = (Vec3::one() - f) * (1.0 - s.metallic);
+    let diffuse = env.irradiance_at(n) * s.base_color;
+    let r = n * (2.0 * n.dot(&v)) - v;
+    let brdf = env.brdf(n_dot_v, s.roughness);
+    let specular = env.prefiltered(r, s.roughness) * (f * brdf.x + Vec3::one() * brdf.y);
+    (kd * diffuse + specular) * s.occlusion
+}
+
+// everything together for a point seen from eye, no shadows
+pub fn shade(
+    s: &PbrSurface,
+    world: Vec3,
+    eye: Vec3,
+    lights: &[Light],
+    env: Option<&Environment>,
+) -> Vec3 {
+    let v = (eye - world).normalize();
+    let mut color = s.emissive;
+    for light in lights {
+        let (l, radiance) = light.incident(world);
+        color += direct(s, v, l, radiance);
+    }
+    if let Some(env) = env {
+        color += ambient(s, v, env);
+    }
+    color
+}
+
+// equirectangular mapping, u goes around the horizon and v from +y down to -y
+pub fn direction_to_uv(d: Vec3) -> Vec2 {
+    Vec2::new(
+        0.5 + d.z.atan2(d.x) / (2.0 * PI),
+        d.y.clamp(-1.0, 1.0).acos() / PI,
+    )
+}
+
+pub fn uv_to_direction(uv: Vec2) -> Vec3 {
+    let phi = (uv.x - 0.5) * 2.0 * PI;
+    let theta = uv.y * PI;
+    Vec3::new(
+        theta.sin() * phi.cos(),
+        theta.cos(),
+        theta.sin() * phi.sin(),
+    )
+}
+
+// bilinear, wrapping around the horizon but clamped at the poles
+fn sample_equirect(t: &Texture, d: Vec3) -> Vec3 {
+    let uv = direction_to_uv(d);
+    let fx = uv.x * t.width as f32 - 0.5;
+    let fy = (uv.y * t.height as f32 - 0.5).clamp(0.0, (t.height - 1) as f32);
+    let (x0, y0) = (fx.floor(), fy.floor());
+    let (tx, ty) = (fx - x0, fy - y0);
+    let (x0, y0) = (x0 as i32, y0 as i32);
+    let y1 = (y0 + 1).min(t.height as i32 - 1);
+    let top = t.texel(x0, y0).lerp(&t.texel(x0 + 1, y0), tx);
+    let bottom = t.texel(x0, y1).lerp(&t.texel(x0 + 1, y1), tx);
+    top.lerp(&bottom, ty)
+}
+
+// low discrepancy points for the importance sampled integrals
+fn hammersley(i: u32, n: u32) -> Vec2 {
+    Vec2::new(
+        i as f32 / n as f32,
+        i.reverse_bits() as f32 / (1u64 << 32) as f32,
+    )
+}
+
+// a microfacet normal around n, distributed like ggx
+fn importance_sample_ggx(xi: Vec2, n: Vec3, roughness: f32) -> Vec3 {
+    let a = roughness * roughness;
+    let phi = 2.0 * PI * xi.x;
+    let cos_theta = ((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+    let up = if n.z.abs() < 0.999 {
+        Vec3::new(0.0, 0.0, 1.0)
+    } else {
+        Vec3::new(1.0, 0.0, 0.0)
+    };
+    let tx = up.cross(&n).normalize();
+    let ty = n.cross(&tx);
+    (tx * (phi.cos() * sin_theta) + ty * (phi.sin() * sin_theta) + n * cos_theta).normalize()
+}
+
+// half size with a 2x2 box filter
+fn downsample(t: &Texture) -> Texture {
+    let (w, h) = ((t.width / 2).max(1), (t.height / 2).max(1));
+    let mut out = Texture::new(w, h, Vec3::zero());
+    for y in 0..h {
+        for x in 0..w {
+            let (sx, sy) = (x as i32 * 2, y as i32 * 2);
+            out.texels[y * w + x] = (t.texel(sx, sy)
+                + t.texel(sx + 1, sy)
+                + t.texel(sx, sy + 1)
+                + t.texel(sx + 1, sy + 1))
+                * 0.25;
+        }
+    }
+    out
+}
+
+pub struct Environment {
+    pub radiance: Texture,
+    // cosine convolved, already divided by pi so it multiplies straight onto albedo
+    pub irradiance: Texture,
+    // level i is prefiltered for roughness i / (levels - 1)
+    pub specular: Vec<Texture>,
+    // split sum scale and bias, indexed by n dot v across and roughness down
+    pub brdf_lut: Vec<Vec2>,
+    pub intensity: f32,
+}
+
+impl Environment {
+    pub fn from_equirect(radiance: Texture) -> Environment {
+        let mut mips = vec![radiance.clone()];
+        while mips.last().unwrap().width > 8 {
+            let next = downsample(mips.last().unwrap());
+            mips.push(next);
+        }
+        let mip_for_width = |width: usize| {
+            mips.iter()
+                .position(|m| m.width <= width)
+                .unwrap_or(mips.len() - 1)
+        };
+
+        // irradiance, brute force over a small copy of the sky
+        let source = &mips[mip_for_width(64)];
+        let mut irradiance = Texture::new(IRRADIANCE_WIDTH, IRRADIANCE_WIDTH / 2, Vec3::zero());
+        let texel_solid_angle = (2.0 * PI / source.width as f32) * (PI / source.height as f32);
+        let source_dirs: Vec<(Vec3, Vec3)> = (0..source.height)
+            .flat_map(|y| (0..source.width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let uv = Vec2::new(
+                    (x as f32 + 0.5) / source.width as f32,
+                    (y as f32 + 0.5) / source.height as f32,
+                );
+                let d = uv_to_direction(uv);
+                let weight = texel_solid_angle * (uv.y * PI).sin();
+                (d, source.texels[y * source.width + x] * weight)
+            })
+            .collect();
+        for y in 0..irradiance.height {
+            for x in 0..irradiance.width {
+                let n = uv_to_direction(Vec2::new(
+                    (x as f32 + 0.5) / irradiance.width as f32,
+                    (y as f32 + 0.5) / irradiance.height as f32,
+                ));
+                let mut sum = Vec3::zero();
+                for (d, l) in &source_dirs {
+                    sum += *l * n.dot(d).max(0.0);
+                }
+                irradiance.texels[y * irradiance.width + x] = sum / PI;
+            }
+        }
+
+        // prefiltered specular, each level half the size of the one before
+        let mut specular = Vec::new();
+        let mut width = radiance.width.min(128);
+        for level in 0..SPECULAR_LEVELS {
+            let roughness = level as f32 / (SPECULAR_LEVELS - 1) as f32;
+            let source = &mips[mip_for_width(width)];
+            if level == 0 {
+                specular.push(source.clone());
+                width = (width / 2).max(8);
+                continue;
+            }
+            let mut out = Texture::new(width, (width / 2).max(1), Vec3::zero());
+            for y in 0..out.height {
+                for x in 0..out.width {
+                    let n = uv_to_direction(Vec2::new(
+                        (x as f32 + 0.5) / out.width as f32,
+                        (y as f32 + 0.5) / out.height as f32,
+                    ));
+                    // assume the view is along the normal, the usual split sum shortcut
+                    let mut sum = Vec3::zero();
+                    let mut weight = 0.0;
+                    for i in 0..SPECULAR_SAMPLES {
+                        let h =
+                            importance_sample_ggx(hammersley(i, SPECULAR_SAMPLES), n, roughness);
+                        let l = h * (2.0 * n.dot(&h)) - n;
+                        let n_dot_l = n.dot(&l);
+                        if n_dot_l > 0.0 {
+                            sum += sample_equirect(source, l) * n_dot_l;
+                            weight += n_dot_l;
+                        }
+                    }
+                    out.texels[y * out.width + x] = sum / weight.max(1e-4);
+                }
+            }
+            specular.push(out);
+            width = (width / 2).max(8);
+        }
+
+        Environment {
+            radiance,
+            irradiance,
+            specular,
+            brdf_lut: brdf_lut(),
+            intensity: 1.0,
+        }
+    }
+
+    // radiance .hdr in equirectangular layout
+    pub fn load(path: &str) -> Result<Environment, String> {
+        Ok(Environment::from_equirect(Texture::load_hdr(path)?))
+    }
+
+    // gradient sky over a dark ground with a bright sun, for when there is no hdr around
+    pub fn sky(sun: Vec3) -> Environment {
+        let sun = sun.normalize();
+        let (w, h) = (128, 64);
+        let mut t = Texture::new(w, h, Vec3::zero());
+        for y in 0..h {
+            for x in 0..w {
+                let d = uv_to_direction(Vec2::new(
+                    (x as f32 + 0.5) / w as f32,
+                    (y as f32 + 0.5) / h as f32,
+                ));
+                let mut c = if d.y > 0.0 {
+                    Vec3::new(0.9, 0.95, 1.0).lerp(&Vec3::new(0.25, 0.45, 0.9), d.y.sqrt())
+                } else {
+                    Vec3::new(0.3, 0.27, 0.25).lerp(&Vec3::new(0.1, 0.09, 0.08), (-d.y).sqrt())
+                };
+                if d.dot(&sun) > 0.995 {
+                    c = Vec3::new(40.0, 38.0, 34.0);
+                }
+                t.texels[y * w + x] = c;
+            }
+        }
+        Environment::from_equirect(t)
+    }
+
+    pub fn background(&self, d: Vec3) -> Vec3 {
+        sample_equirect(&self.radiance, d) * self.intensity
+    }
+
+    pub fn irradiance_at(&self, n: Vec3) -> Vec3 {
+        sample_equirect(&self.irradiance, n) * self.intensity
+    }
+
+    // blend the two nearest roughness levels
+    pub fn prefiltered(&self, r: Vec3, roughness: f32) -> Vec3 {
+        let level = roughness.clamp(0.0, 1.0) * (self.specular.len() - 1) as f32;
+        let lo = level.floor() as usize;
+        let hi = (lo + 1).min(self.specular.len() - 1);
+        let uv = direction_to_uv(r);
+        let a = self.specular[lo].sample_bilinear(uv);
+        let b = self.specular[hi].sample_bilinear(uv);
+        a.lerp(&b, level - lo as f32) * self.intensity
+    }
+
+    pub fn brdf(&self, n_dot_v: f32, roughness: f32) -> Vec2 {
+        let x = ((n_dot_v * BRDF_LUT_SIZE as f32) as usize).min(BRDF_LUT_SIZE - 1);
+        let y = ((roughness * BRDF_LUT_SIZE as f32) as usize).min(BRDF_LUT_SIZE - 1);
+        self.brdf_lut[y * BRDF_LUT_SIZE + x]
+    }
+}
+
+// integrate the specular brdf against white light, giving a scale and bias on f0
+fn brdf_lut() -> Vec<Vec2> {
+    let mut lut = vec![Vec2::zero(); BRDF_LUT_SIZE * BRDF_LUT_SIZE];
+    let n = Vec3::new(0.0, 0.0, 1.0);
+    for y in 0..BRDF_LUT_SIZE {
+        let roughness = (y as f32 + 0.5) / BRDF_LUT_SIZE as f32;
+        let k = roughness * roughness / 2.0;
+        for x in 0..BRDF_LUT_SIZE {
+            let n_dot_v = (x as f32 + 0.5) / BRDF_LUT_SIZE as f32;
+            let v = Vec3::new((1.0 - n_dot_v * n_dot_v).sqrt(), 0.0, n_dot_v);
+            let (mut scale, mut bias) = (0.0, 0.0);
+            for i in 0..BRDF_SAMPLES {
+                let h = importance_sample_ggx(hammersley(i, BRDF_SAMPLES), n, roughness);
+                let l = h * (2.0 * v.dot(&h)) - v;
+                let n_dot_l = l.z.max(0.0);
+                if n_dot_l > 0.0 {
+                    let n_dot_h = h.z.max(0.0);
+                    let v_dot_h = v.dot(&h).max(0.0);
+                    let g = geometry_smith(n_dot_v, n_dot_l, k);
+                    let g_vis = g * v_dot_h / (n_dot_h * n_dot_v).max(1e-4);
+                    let fc = (1.0 - v_dot_h).powi(5);
+                    scale += (1.0 - fc) * g_vis;
+                    bias += fc * g_vis;
+                }
+            }
+            lut[y * BRDF_LUT_SIZE + x] =
+                Vec2::new(scale / BRDF_SAMPLES as f32, bias / BRDF_SAMPLES as f32);
+        }
+    }
+    lut
+}
@@ -0,0 +1,154 @@
+/*
+    picking
+    what's under the mouse, either by casting the camera's ray against the meshes
+    or by rasterizing object ids into a buffer and reading back the pixel.
+    the ray is cheaper for one click, the id buffer agrees with the rendered image
+    down to the pixel, edges, clipping and culling included
+*/
+
+use crate::framebuffer::Framebuffer;
+use crate::mesh::Mesh;
+use crate::mm::{Mat4, Ray, Triangle, Vec3};
+use crate::raster::{draw_triangle, Fragment};
+use crate::state::{Compare, RenderState, StencilState};
+
+#[derive(Debug, Copy, Clone)]
+pub struct Hit {
+    // index into the list of objects that was picked from
+    pub object: usize,
+    // index into the mesh's triangle list
+    pub triangle: usize,
+    // weights of the triangle's three corners at the hit
+    pub barycentric: Vec3,
+    pub point: Vec3,
+    // along the ray from its origin
+    pub distance: f32,
+}
+
+// closest hit on one mesh, the bounding box goes first so misses stay cheap
+pub fn pick_mesh(ray: &Ray, mesh: &Mesh, model: &Mat4, object: usize) -> Option<Hit> {
+    ray.intersect_aabb(&mesh.bounds().transform(model))?;
+    let world = mesh.world_positions(model);
+    let mut best: Option<Hit> = None;
+    for (triangle, tri) in mesh.indices.iter().enumerate() {
+        let corners = Triangle::new(world[tri[0]], world[tri[1]], world[tri[2]]);
+        if let Some((t, u, v)) = ray.intersect_triangle(&corners) {
+            if best.is_none_or(|b| t < b.distance) {
+                best = Some(Hit {
+                    object,
+                    triangle,
+                    barycentric: Vec3::new(1.0 - u - v, u, v),
+                    point: ray.at(t),
+                    distance: t,
+                });
+            }
+        }
+    }
+    best
+}
+
+// closest hit across a list of meshes and their model matrices
+pub fn pick(ray: &Ray, objects: &[(&Mesh, Mat4)]) -> Option<Hit> {
+    objects
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (mesh, model))| pick_mesh(ray, mesh, model, i))
+        .min_by(|a, b| a.distance.total_cmp(&b.distance))
+}
+
+// nothing drawn here
+const NO_OBJECT: u32 = u32::MAX;
+
+pub struct IdBuffer {
+    pub width: usize,
+    pub height: usize,
+    // depth for the id pass, its color plane goes unused
+    pub target: Framebuffer,
+    pub objects: Vec<u32>,
+    pub triangles: Vec<u32>,
+    pub barycentrics: Vec<Vec3>,
+    pub positions: Vec<Vec3>,
+}
+
+impl IdBuffer {
+    pub fn new(width: usize, height: usize) -> IdBuffer {
+        let n = width * height;
+        IdBuffer {
+            width,
+            height,
+            target: Framebuffer::new(width, height),
+            objects: vec![NO_OBJECT; n],
+            triangles: vec![0; n],
+            barycentrics: vec![Vec3::zero(); n],
+            positions: vec![Vec3::zero(); n],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.target.clear(Vec3::zero());
+        self.objects.fill(NO_OBJECT);
+    }
+
+    // draws the mesh the way the frame would, tagging each pixel it wins with the object
+    // every triangle's corners are colored red, green and blue, so the interpolated
+    // color comes out as the barycentrics for free, interpolated the way the frame is
+    pub fn draw(
+        &mut self,
+        state: &RenderState,
+        mesh: &Mesh,
+        model: &Mat4,
+        view_proj: &Mat4,
+        object: usize,
+    ) {
+        let width = self.width;
+        // culling, vertex snapping and affine interpolation follow the frame, but the
+        // nearest surface always wins, which is what sorting gets to without a depth buffer
+        let state = RenderState {
+            depth_test: true,
+            depth_write: true,
+            depth_func: Compare::Less,
+            color_write: false,
+            stencil: StencilState::disabled(),
+            ..*state
+        };
+        let vertices = mesh.vertices(model, view_proj);
+        let corners = [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        ];
+        for (triangle, tri) in mesh.indices.iter().enumerate() {
+            let mut verts = [vertices[tri[0]], vertices[tri[1]], vertices[tri[2]]];
+            for (v, c) in verts.iter_mut().zip(corners) {
+                v.varying.color = c;
+            }
+            draw_triangle(&mut self.target, &state, &verts, &mut |frag: &Fragment| {
+                let i = frag.y * width + frag.x;
+                self.objects[i] = object as u32;
+                self.triangles[i] = triangle as u32;
+                self.barycentrics[i] = frag.varying.color;
+                self.positions[i] = frag.varying.world;
+                Some(Vec3::zero())
+            });
+        }
+    }
+
+    // what was drawn at a pixel, distance is measured along the ray through it
+    pub fn pick(&self, x: usize, y: usize, ray: &Ray) -> Option<Hit> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let i = y * self.width + x;
+        if self.objects[i] == NO_OBJECT {
+            return None;
+        }
+        let point = self.positions[i];
+        Some(Hit {
+            object: self.objects[i] as usize,
+            triangle: self.triangles[i] as usize,
+            barycentric: self.barycentrics[i],
+            point,
+            distance: (point - ray.origin).dot(&ray.direction),
+        })
+    }
+}
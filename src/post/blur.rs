@@ -0,0 +1,97 @@
+/*
+    blur
+    separable box and gaussian blurs, one horizontal and one vertical pass
+*/
+
+use super::{fetch, PostProcess};
+use crate::framebuffer::Framebuffer;
+use crate::mm::Vec3;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BlurKind {
+    Box,
+    Gaussian { sigma: f32 },
+}
+
+pub struct Blur {
+    pub kind: BlurKind,
+    pub radius: usize,
+}
+
+impl Blur {
+    pub fn boxed(radius: usize) -> Blur {
+        Blur {
+            kind: BlurKind::Box,
+            radius,
+        }
+    }
+
+    pub fn gaussian(radius: usize, sigma: f32) -> Blur {
+        Blur {
+            kind: BlurKind::Gaussian { sigma },
+            radius,
+        }
+    }
+
+    pub fn kernel(&self) -> Vec<f32> {
+        match self.kind {
+            BlurKind::Box => vec![1.0 / (2 * self.radius + 1) as f32; 2 * self.radius + 1],
+            BlurKind::Gaussian { sigma } => gaussian_kernel(self.radius, sigma),
+        }
+    }
+}
+
+impl Default for Blur {
+    fn default() -> Blur {
+        Blur::gaussian(2, 1.0)
+    }
+}
+
+// normalized 1d gaussian weights from -radius to radius
+pub fn gaussian_kernel(radius: usize, sigma: f32) -> Vec<f32> {
+    let r = radius as i32;
+    let weights: Vec<f32> = (-r..=r)
+        .map(|i| (-((i * i) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let sum: f32 = weights.iter().sum();
+    weights.iter().map(|w| w / sum).collect()
+}
+
+// run a 1d kernel across rows then down columns, clamping at the edges
+pub fn blur_separable(color: &[Vec3], width: usize, height: usize, kernel: &[f32]) -> Vec<Vec3> {
+    let r = (kernel.len() / 2) as i32;
+    let mut horizontal = vec![Vec3::zero(); color.len()];
+    for y in 0..height as i32 {
+        for x in 0..width as i32 {
+            let mut sum = Vec3::zero();
+            for (k, w) in kernel.iter().enumerate() {
+                sum += fetch(color, width, height, x + k as i32 - r, y) * *w;
+            }
+            horizontal[y as usize * width + x as usize] = sum;
+        }
+    }
+    let mut out = vec![Vec3::zero(); color.len()];
+    for y in 0..height as i32 {
+        for x in 0..width as i32 {
+            let mut sum = Vec3::zero();
+            for (k, w) in kernel.iter().enumerate() {
+                sum += fetch(&horizontal, width, height, x, y + k as i32 - r) * *w;
+            }
+            out[y as usize * width + x as usize] = sum;
+        }
+    }
+    out
+}
+
+impl PostProcess for Blur {
+    fn name(&self) -> &str {
+        match self.kind {
+            BlurKind::Box => "box blur",
+            BlurKind::Gaussian { .. } => "gaussian blur",
+        }
+    }
+
+    fn apply(&mut self, fb: &mut Framebuffer) {
+        fb.color = blur_separable(&fb.color, fb.width, fb.height, &self.kernel());
+    }
+}
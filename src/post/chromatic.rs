@@ -0,0 +1,51 @@
+/*
+    chromatic aberration
+    red and blue are sampled pushed out and pulled in from the screen center
+    like a cheap lens that focuses each wavelength at a different scale
+*/
+
+use super::{sample_bilinear, PostProcess};
+use crate::framebuffer::Framebuffer;
+use crate::mm::Vec3;
+
+pub struct ChromaticAberration {
+    // scale difference between channels at the edge of the screen, 0.01 is subtle
+    pub strength: f32,
+}
+
+impl ChromaticAberration {
+    pub fn new(strength: f32) -> ChromaticAberration {
+        ChromaticAberration { strength }
+    }
+}
+
+impl Default for ChromaticAberration {
+    fn default() -> ChromaticAberration {
+        ChromaticAberration::new(0.015)
+    }
+}
+
+impl PostProcess for ChromaticAberration {
+    fn name(&self) -> &str {
+        "chromatic aberration"
+    }
+
+    fn apply(&mut self, fb: &mut Framebuffer) {
+        let (width, height) = (fb.width, fb.height);
+        let src = fb.color.clone();
+        let cx = width as f32 / 2.0;
+        let cy = height as f32 / 2.0;
+        for y in 0..height {
+            for x in 0..width {
+                let dx = x as f32 + 0.5 - cx;
+                let dy = y as f32 + 0.5 - cy;
+                let outer = 1.0 + self.strength;
+                let inner = 1.0 - self.strength;
+                let r = sample_bilinear(&src, width, height, cx + dx * outer, cy + dy * outer);
+                let b = sample_bilinear(&src, width, height, cx + dx * inner, cy + dy * inner);
+                let i = fb.index(x, y);
+                fb.color[i] = Vec3::new(r.x, src[i].y, b.z);
+            }
+        }
+    }
+}
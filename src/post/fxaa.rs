@@ -0,0 +1,165 @@
+/*
+    fxaa
+    a cpu port of the fxaa 3.11 quality path
+    finds the local edge direction from luma, walks along the edge to its ends
+    and resamples the pixel across the edge by how far it sits from them
+*/
+
+use super::{luma, sample_bilinear, PostProcess};
+use crate::framebuffer::Framebuffer;
+
+// how far to step along the edge on each search iteration
+const QUALITY: [f32; 12] = [1.0, 1.0, 1.0, 1.0, 1.0, 1.5, 2.0, 2.0, 2.0, 2.0, 4.0, 8.0];
+
+pub struct Fxaa {
+    // minimum local contrast, relative to the brightest neighbor, to count as an edge
+    pub edge_threshold: f32,
+    // absolute contrast floor so dark areas are left alone
+    pub edge_threshold_min: f32,
+    // amount of sub pixel aliasing removal, 0 is off and 1 is softest
+    pub subpixel: f32,
+}
+
+impl Fxaa {
+    pub fn new() -> Fxaa {
+        Fxaa {
+            edge_threshold: 0.125,
+            edge_threshold_min: 0.0312,
+            subpixel: 0.75,
+        }
+    }
+}
+
+impl Default for Fxaa {
+    fn default() -> Fxaa {
+        Fxaa::new()
+    }
+}
+
+impl PostProcess for Fxaa {
+    fn name(&self) -> &str {
+        "fxaa"
+    }
+
+    fn apply(&mut self, fb: &mut Framebuffer) {
+        let (width, height) = (fb.width, fb.height);
+        let src = fb.color.clone();
+        let lumas: Vec<f32> = src.iter().map(|&c| luma(c)).collect();
+        let l = |x: i32, y: i32| {
+            let x = x.clamp(0, width as i32 - 1) as usize;
+            let y = y.clamp(0, height as i32 - 1) as usize;
+            lumas[y * width + x]
+        };
+        let l_at = |x: f32, y: f32| luma(sample_bilinear(&src, width, height, x, y));
+
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                let m = l(x, y);
+                let n = l(x, y - 1);
+                let s = l(x, y + 1);
+                let e = l(x + 1, y);
+                let w = l(x - 1, y);
+
+                let range_max = m.max(n).max(s).max(e).max(w);
+                let range_min = m.min(n).min(s).min(e).min(w);
+                let range = range_max - range_min;
+                if range < self.edge_threshold_min.max(range_max * self.edge_threshold) {
+                    continue;
+                }
+
+                let nw = l(x - 1, y - 1);
+                let ne = l(x + 1, y - 1);
+                let sw = l(x - 1, y + 1);
+                let se = l(x + 1, y + 1);
+
+                // horizontal edges change the most going vertically
+                let edge_horz = (-2.0 * w + nw + sw).abs()
+                    + (-2.0 * m + n + s).abs() * 2.0
+                    + (-2.0 * e + ne + se).abs();
+                let edge_vert = (-2.0 * n + nw + ne).abs()
+                    + (-2.0 * m + w + e).abs() * 2.0
+                    + (-2.0 * s + sw + se).abs();
+                let horizontal = edge_horz >= edge_vert;
+
+                // pick the side of the edge with the steeper gradient
+                let (luma1, luma2) = if horizontal { (n, s) } else { (w, e) };
+                let gradient1 = luma1 - m;
+                let gradient2 = luma2 - m;
+                let steepest1 = gradient1.abs() >= gradient2.abs();
+                let gradient_scaled = 0.25 * gradient1.abs().max(gradient2.abs());
+                let (step, local_average) = if steepest1 {
+                    (-1.0, 0.5 * (luma1 + m))
+                } else {
+                    (1.0, 0.5 * (luma2 + m))
+                };
+
+                // start half a pixel over, right on the edge
+                let cx = x as f32 + 0.5;
+                let cy = y as f32 + 0.5;
+                let (start_x, start_y) = if horizontal {
+                    (cx, cy + step * 0.5)
+                } else {
+                    (cx + step * 0.5, cy)
+                };
+                let (dx, dy) = if horizontal { (1.0, 0.0) } else { (0.0, 1.0) };
+
+                // walk both ways until the luma along the edge stops matching
+                let mut p1 = (start_x - dx, start_y - dy);
+                let mut p2 = (start_x + dx, start_y + dy);
+                let mut end1 = l_at(p1.0, p1.1) - local_average;
+                let mut end2 = l_at(p2.0, p2.1) - local_average;
+                let mut reached1 = end1.abs() >= gradient_scaled;
+                let mut reached2 = end2.abs() >= gradient_scaled;
+                for q in QUALITY.iter().skip(1) {
+                    if reached1 && reached2 {
+                        break;
+                    }
+                    if !reached1 {
+                        p1 = (p1.0 - dx * q, p1.1 - dy * q);
+                        end1 = l_at(p1.0, p1.1) - local_average;
+                        reached1 = end1.abs() >= gradient_scaled;
+                    }
+                    if !reached2 {
+                        p2 = (p2.0 + dx * q, p2.1 + dy * q);
+                        end2 = l_at(p2.0, p2.1) - local_average;
+                        reached2 = end2.abs() >= gradient_scaled;
+                    }
+                }
+
+                let (distance1, distance2) = if horizontal {
+                    (cx - p1.0, p2.0 - cx)
+                } else {
+                    (cy - p1.1, p2.1 - cy)
+                };
+                let direction1 = distance1 < distance2;
+                let distance = distance1.min(distance2);
+                let thickness = distance1 + distance2;
+                let pixel_offset = -distance / thickness + 0.5;
+
+                // only shift if the end we stopped at agrees with the center's side
+                let center_smaller = m < local_average;
+                let end = if direction1 { end1 } else { end2 };
+                let edge_offset = if (end < 0.0) != center_smaller {
+                    pixel_offset
+                } else {
+                    0.0
+                };
+
+                // sub pixel aliasing, for single pixel features the edge search misses
+                let average = (2.0 * (n + s + e + w) + nw + ne + sw + se) / 12.0;
+                let sub1 = ((average - m).abs() / range).clamp(0.0, 1.0);
+                let sub2 = (-2.0 * sub1 + 3.0) * sub1 * sub1;
+                let sub_offset = sub2 * sub2 * self.subpixel;
+
+                let offset = edge_offset.max(sub_offset) * step;
+                let (sx, sy) = if horizontal {
+                    (cx, cy + offset)
+                } else {
+                    (cx + offset, cy)
+                };
+                let i = fb.index(x as usize, y as usize);
+                fb.color[i] = sample_bilinear(&src, width, height, sx, sy);
+            }
+        }
+    }
+}
@@ -0,0 +1,41 @@
+/*
+    gamma correction
+    the rasterizer works in linear color, this encodes it for display
+*/
+
+use super::PostProcess;
+use crate::framebuffer::Framebuffer;
+use crate::mm::Vec3;
+
+pub struct Gamma {
+    pub gamma: f32,
+}
+
+impl Gamma {
+    pub fn new(gamma: f32) -> Gamma {
+        Gamma { gamma }
+    }
+}
+
+impl Default for Gamma {
+    fn default() -> Gamma {
+        Gamma::new(2.2)
+    }
+}
+
+impl PostProcess for Gamma {
+    fn name(&self) -> &str {
+        "gamma"
+    }
+
+    fn apply(&mut self, fb: &mut Framebuffer) {
+        let inv = 1.0 / self.gamma;
+        for c in fb.color.iter_mut() {
+            *c = Vec3::new(
+                c.x.max(0.0).powf(inv),
+                c.y.max(0.0).powf(inv),
+                c.z.max(0.0).powf(inv),
+            );
+        }
+    }
+}
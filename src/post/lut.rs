@@ -0,0 +1,234 @@
+/*
+    3d lut color grading
+    loads adobe / resolve style .cube files and remaps every pixel through
+    the cube with trilinear filtering
+*/
+
+use super::PostProcess;
+use crate::framebuffer::Framebuffer;
+use crate::mm::Vec3;
+
+pub struct Lut {
+    pub size: usize,
+    pub domain_min: Vec3,
+    pub domain_max: Vec3,
+    // red changes fastest, then green, then blue, same as the file
+    pub table: Vec<Vec3>,
+}
+
+fn parse_vec3(parts: &[&str], line: usize) -> Result<Vec3, String> {
+    if parts.len() != 3 {
+        return Err(format!("line {}: expected 3 values", line));
+    }
+    let mut v = [0.0; 3];
+    for (i, part) in parts.iter().enumerate() {
+        v[i] = part
+            .parse::<f32>()
+            .map_err(|e| format!("line {}: {}", line, e))?;
+    }
+    Ok(Vec3::new(v[0], v[1], v[2]))
+}
+
+fn parse_range(parts: &[&str], line: usize) -> Result<(f32, f32), String> {
+    if parts.len() != 2 {
+        return Err(format!("line {}: expected 2 values", line));
+    }
+    let min = parts[0]
+        .parse::<f32>()
+        .map_err(|e| format!("line {}: {}", line, e))?;
+    let max = parts[1]
+        .parse::<f32>()
+        .map_err(|e| format!("line {}: {}", line, e))?;
+    Ok((min, max))
+}
+
+impl Lut {
+    // a lut that leaves colors as they are
+    pub fn identity(size: usize) -> Result<Lut, String> {
+        if size < 2 {
+            return Err("lut size must be at least 2".to_string());
+        }
+        let mut table = Vec::with_capacity(size * size * size);
+        let scale = 1.0 / (size - 1) as f32;
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    table.push(Vec3::new(r as f32, g as f32, b as f32) * scale);
+                }
+            }
+        }
+        Ok(Lut {
+            size,
+            domain_min: Vec3::zero(),
+            domain_max: Vec3::one(),
+            table,
+        })
+    }
+
+    pub fn parse(text: &str) -> Result<Lut, String> {
+        let mut size = 0;
+        let mut domain_min = Vec3::zero();
+        let mut domain_max = Vec3::one();
+        let mut table = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let n = n + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            match parts[0] {
+                "TITLE" => {}
+                "LUT_3D_SIZE" => {
+                    size = parts
+                        .get(1)
+                        .ok_or(format!("line {}: missing size", n))?
+                        .parse::<usize>()
+                        .map_err(|e| format!("line {}: {}", n, e))?;
+                    if size < 2 {
+                        return Err(format!("line {}: lut size must be at least 2", n));
+                    }
+                }
+                "LUT_1D_SIZE" => return Err("1d luts are not supported".to_string()),
+                "DOMAIN_MIN" => domain_min = parse_vec3(&parts[1..], n)?,
+                "DOMAIN_MAX" => domain_max = parse_vec3(&parts[1..], n)?,
+                // resolve's way of writing the domain, one range for all three channels
+                "LUT_3D_INPUT_RANGE" => {
+                    let (min, max) = parse_range(&parts[1..], n)?;
+                    domain_min = Vec3::one() * min;
+                    domain_max = Vec3::one() * max;
+                }
+                // only matters for a 1d shaper, which gets refused above
+                "LUT_1D_INPUT_RANGE" => {}
+                _ => table.push(parse_vec3(&parts, n)?),
+            }
+        }
+        if size == 0 {
+            return Err("missing LUT_3D_SIZE".to_string());
+        }
+        if (0..3).any(|i| domain_max[i] <= domain_min[i]) {
+            return Err("the domain max must be above the min on every channel".to_string());
+        }
+        if table.len() != size * size * size {
+            return Err(format!(
+                "expected {} entries, found {}",
+                size * size * size,
+                table.len()
+            ));
+        }
+        Ok(Lut {
+            size,
+            domain_min,
+            domain_max,
+            table,
+        })
+    }
+
+    pub fn load(path: &str) -> Result<Lut, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Lut::parse(&text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    fn at(&self, r: usize, g: usize, b: usize) -> Vec3 {
+        self.table[(b * self.size + g) * self.size + r]
+    }
+
+    pub fn lookup(&self, c: Vec3) -> Vec3 {
+        // a hand built table too small to filter passes colors through
+        if self.size < 2 {
+            return c;
+        }
+        let n = (self.size - 1) as f32;
+        let range = self.domain_max - self.domain_min;
+        let t = (c - self.domain_min) / range;
+        let fr = t.x.clamp(0.0, 1.0) * n;
+        let fg = t.y.clamp(0.0, 1.0) * n;
+        let fb = t.z.clamp(0.0, 1.0) * n;
+        let r0 = (fr.floor() as usize).min(self.size - 2);
+        let g0 = (fg.floor() as usize).min(self.size - 2);
+        let b0 = (fb.floor() as usize).min(self.size - 2);
+        let tr = fr - r0 as f32;
+        let tg = fg - g0 as f32;
+        let tb = fb - b0 as f32;
+
+        let lerp_r = |g: usize, b: usize| self.at(r0, g, b).lerp(&self.at(r0 + 1, g, b), tr);
+        let front = lerp_r(g0, b0).lerp(&lerp_r(g0 + 1, b0), tg);
+        let back = lerp_r(g0, b0 + 1).lerp(&lerp_r(g0 + 1, b0 + 1), tg);
+        front.lerp(&back, tb)
+    }
+}
+
+impl PostProcess for Lut {
+    fn name(&self) -> &str {
+        "lut"
+    }
+
+    fn apply(&mut self, fb: &mut Framebuffer) {
+        for c in fb.color.iter_mut() {
+            *c = self.lookup(*c);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Vec3, b: Vec3) -> bool {
+        (a - b).length() < 1e-4
+    }
+
+    // a size 2 cube that inverts every channel
+    const INVERT: &str = "TITLE \"invert\"\n\
+        # comment\n\
+        LUT_3D_SIZE 2\n\
+        \n\
+        1 1 1\n0 1 1\n1 0 1\n0 0 1\n\
+        1 1 0\n0 1 0\n1 0 0\n0 0 0\n";
+
+    #[test]
+    fn identity_passes_colors_through() {
+        let lut = Lut::identity(17).unwrap();
+        for c in [Vec3::zero(), Vec3::one(), Vec3::new(0.2, 0.55, 0.9)] {
+            assert!(close(lut.lookup(c), c));
+        }
+        assert!(Lut::identity(1).is_err());
+        assert!(Lut::identity(0).is_err());
+    }
+
+    #[test]
+    fn parse_and_lookup() {
+        let lut = Lut::parse(INVERT).unwrap();
+        assert_eq!(lut.size, 2);
+        let c = Vec3::new(0.25, 0.5, 1.0);
+        assert!(close(lut.lookup(c), Vec3::one() - c));
+        // out of the domain clamps to its edge
+        assert!(close(lut.lookup(Vec3::one() * 2.0), Vec3::zero()));
+    }
+
+    #[test]
+    fn parse_domains() {
+        let text = format!("DOMAIN_MIN 0 0 0\nDOMAIN_MAX 2 2 2\n{}", INVERT);
+        let lut = Lut::parse(&text).unwrap();
+        assert!(close(lut.lookup(Vec3::one()), Vec3::one() * 0.5));
+        let text = format!("LUT_3D_INPUT_RANGE 0 2\nLUT_1D_INPUT_RANGE 0 1\n{}", INVERT);
+        let lut = Lut::parse(&text).unwrap();
+        assert!(close(lut.domain_max, Vec3::one() * 2.0));
+        assert!(close(lut.lookup(Vec3::one()), Vec3::one() * 0.5));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Lut::parse("LUT_3D_SIZE 1\n0 0 0\n").is_err());
+        assert!(Lut::parse("LUT_3D_SIZE 2\n0 0 0\n").is_err());
+        assert!(Lut::parse("0 0 0\n").is_err());
+        assert!(Lut::parse("LUT_1D_SIZE 2\n0 0 0\n1 1 1\n").is_err());
+        assert!(Lut::parse("LUT_3D_INPUT_RANGE 0\n").is_err());
+        let flat = format!("DOMAIN_MIN 0 0 0\nDOMAIN_MAX 1 0 1\n{}", INVERT);
+        assert!(Lut::parse(&flat).is_err());
+        let inverted = format!("LUT_3D_INPUT_RANGE 1 0\n{}", INVERT);
+        assert!(Lut::parse(&inverted).is_err());
+        let err = Lut::parse("LUT_3D_SIZE 2\n0 0 x\n").err().unwrap();
+        assert!(err.starts_with("line 2:"), "{}", err);
+    }
+}
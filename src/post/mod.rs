@@ -0,0 +1,164 @@
+/*
+    post processing
+    effects that run over the resolved framebuffer after rasterization
+    and before the viewer uploads it to raylib
+*/
+
+pub mod blur;
+pub mod chromatic;
+pub mod fxaa;
+pub mod gamma;
+pub mod lut;
+pub mod quantize;
+pub mod rgb555;
+pub mod sharpen;
+pub mod smaa;
+pub mod ssao;
+pub mod vi;
+pub mod vignette;
+
+use crate::framebuffer::Framebuffer;
+use crate::mm::{Mat4, Vec3};
+
+// an effect gets the whole framebuffer, color plus depth and stencil
+// the color plane is already resolved when the chain runs
+pub trait PostProcess {
+    fn name(&self) -> &str;
+    fn apply(&mut self, fb: &mut Framebuffer);
+    // effects that work in 3d get the camera before each run, the rest ignore it
+    fn set_view(&mut self, _view_proj: &Mat4) {}
+}
+
+pub struct PostEntry {
+    pub effect: Box<dyn PostProcess>,
+    pub enabled: bool,
+}
+
+// effects run in order, disabled ones are skipped but keep their slot
+pub struct PostChain {
+    pub entries: Vec<PostEntry>,
+}
+
+impl PostChain {
+    pub fn new() -> PostChain {
+        PostChain {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, effect: Box<dyn PostProcess>) {
+        self.entries.push(PostEntry {
+            effect,
+            enabled: true,
+        });
+    }
+
+    pub fn insert(&mut self, index: usize, effect: Box<dyn PostProcess>) {
+        self.entries.insert(
+            index,
+            PostEntry {
+                effect,
+                enabled: true,
+            },
+        );
+    }
+
+    // swap an effect for a new one in the same slot, keeping its enabled state
+    pub fn replace(&mut self, name: &str, effect: Box<dyn PostProcess>) -> bool {
+        match self.find(name) {
+            Some(index) => {
+                self.entries[index].effect = effect;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|e| e.effect.name() == name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Box<dyn PostProcess>> {
+        let index = self.find(name)?;
+        Some(self.entries.remove(index).effect)
+    }
+
+    // returns false if there is no effect with that name
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        match self.find(name) {
+            Some(index) => {
+                self.entries[index].enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // returns the new state, or None if there is no effect with that name
+    pub fn toggle(&mut self, name: &str) -> Option<bool> {
+        let index = self.find(name)?;
+        let entry = &mut self.entries[index];
+        entry.enabled = !entry.enabled;
+        Some(entry.enabled)
+    }
+
+    pub fn enabled_names(&self) -> Vec<&str> {
+        self.entries
+            .iter()
+            .filter(|e| e.enabled)
+            .map(|e| e.effect.name())
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn set_view(&mut self, view_proj: &Mat4) {
+        for entry in self.entries.iter_mut() {
+            entry.effect.set_view(view_proj);
+        }
+    }
+
+    pub fn run(&mut self, fb: &mut Framebuffer) {
+        for entry in self.entries.iter_mut() {
+            if entry.enabled {
+                entry.effect.apply(fb);
+            }
+        }
+    }
+}
+
+impl Default for PostChain {
+    fn default() -> PostChain {
+        PostChain::new()
+    }
+}
+
+// perceptual brightness, rec 601 weights
+pub fn luma(c: Vec3) -> f32 {
+    c.x * 0.299 + c.y * 0.587 + c.z * 0.114
+}
+
+// read a pixel, clamping coordinates to the edge of the image
+pub fn fetch(color: &[Vec3], width: usize, height: usize, x: i32, y: i32) -> Vec3 {
+    let x = x.clamp(0, width as i32 - 1) as usize;
+    let y = y.clamp(0, height as i32 - 1) as usize;
+    color[y * width + x]
+}
+
+// bilinear sample in pixel units, pixel centers sit at +0.5
+pub fn sample_bilinear(color: &[Vec3], width: usize, height: usize, x: f32, y: f32) -> Vec3 {
+    let fx = x - 0.5;
+    let fy = y - 0.5;
+    let x0 = fx.floor();
+    let y0 = fy.floor();
+    let tx = fx - x0;
+    let ty = fy - y0;
+    let (x0, y0) = (x0 as i32, y0 as i32);
+    let top =
+        fetch(color, width, height, x0, y0).lerp(&fetch(color, width, height, x0 + 1, y0), tx);
+    let bottom = fetch(color, width, height, x0, y0 + 1)
+        .lerp(&fetch(color, width, height, x0 + 1, y0 + 1), tx);
+    top.lerp(&bottom, ty)
+}
@@ -0,0 +1,141 @@
+/*
+    palette quantization
+    snaps every pixel to the closest palette color, with ordered, blue noise
+    or error diffusion dithering to fake the in between shades
+*/
+
+use super::PostProcess;
+use crate::framebuffer::Framebuffer;
+use crate::mm::Vec3;
+use crate::noise::{bayer_matrix, blue_noise};
+use crate::palette::Palette;
+
+const BLUE_NOISE_SIZE: usize = 32;
+
+// the ordered dither matrices there are, bayer_matrix takes any power of two
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BayerSize {
+    Two,
+    Four,
+    Eight,
+}
+
+impl BayerSize {
+    pub fn size(&self) -> usize {
+        match self {
+            BayerSize::Two => 2,
+            BayerSize::Four => 4,
+            BayerSize::Eight => 8,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Dither {
+    None,
+    // ordered, with a 2x2, 4x4 or 8x8 matrix
+    Bayer(BayerSize),
+    BlueNoise,
+    FloydSteinberg,
+    Atkinson,
+}
+
+pub struct Quantize {
+    pub palette: Palette,
+    pub dither: Dither,
+    // how far ordered dithering pushes a color before snapping it
+    pub spread: f32,
+    // threshold map for the ordered modes, tiled across the screen
+    thresholds: Vec<f32>,
+    threshold_size: usize,
+}
+
+impl Quantize {
+    pub fn new(palette: Palette, dither: Dither) -> Quantize {
+        let spread = palette.spacing();
+        let (thresholds, threshold_size) = match dither {
+            Dither::Bayer(size) => (bayer_matrix(size.size()), size.size()),
+            Dither::BlueNoise => (blue_noise(BLUE_NOISE_SIZE, 1), BLUE_NOISE_SIZE),
+            _ => (Vec::new(), 0),
+        };
+        Quantize {
+            palette,
+            dither,
+            spread,
+            thresholds,
+            threshold_size,
+        }
+    }
+
+    fn ordered(&self, fb: &mut Framebuffer) {
+        let n = self.threshold_size;
+        for y in 0..fb.height {
+            for x in 0..fb.width {
+                let t = self.thresholds[(y % n) * n + x % n] - 0.5;
+                let i = fb.index(x, y);
+                fb.color[i] = self
+                    .palette
+                    .nearest(fb.color[i] + Vec3::one() * (t * self.spread));
+            }
+        }
+    }
+
+    // push each pixel's rounding error onto the neighbors that are not done yet
+    fn diffuse(&self, fb: &mut Framebuffer, taps: &[(i32, i32, f32)]) {
+        let (width, height) = (fb.width as i32, fb.height as i32);
+        let mut work = fb.color.clone();
+        for y in 0..height {
+            for x in 0..width {
+                let i = (y * width + x) as usize;
+                let old = work[i];
+                let new = self.palette.nearest(old);
+                fb.color[i] = new;
+                let error = old - new;
+                for &(dx, dy, w) in taps {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if nx >= 0 && nx < width && ny < height {
+                        work[(ny * width + nx) as usize] += error * w;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl PostProcess for Quantize {
+    fn name(&self) -> &str {
+        "palette"
+    }
+
+    fn apply(&mut self, fb: &mut Framebuffer) {
+        match self.dither {
+            Dither::None => {
+                for c in fb.color.iter_mut() {
+                    *c = self.palette.nearest(*c);
+                }
+            }
+            Dither::Bayer(_) | Dither::BlueNoise => self.ordered(fb),
+            Dither::FloydSteinberg => self.diffuse(
+                fb,
+                &[
+                    (1, 0, 7.0 / 16.0),
+                    (-1, 1, 3.0 / 16.0),
+                    (0, 1, 5.0 / 16.0),
+                    (1, 1, 1.0 / 16.0),
+                ],
+            ),
+            // only passes on 6/8 of the error, which keeps contrast up
+            Dither::Atkinson => self.diffuse(
+                fb,
+                &[
+                    (1, 0, 1.0 / 8.0),
+                    (2, 0, 1.0 / 8.0),
+                    (-1, 1, 1.0 / 8.0),
+                    (0, 1, 1.0 / 8.0),
+                    (1, 1, 1.0 / 8.0),
+                    (0, 2, 1.0 / 8.0),
+                ],
+            ),
+        }
+    }
+}
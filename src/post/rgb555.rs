@@ -0,0 +1,61 @@
+/*
+    15 bit color
+    drops every channel to 5 bits the way the ps1 gpu did, with its 4x4
+    ordered dither added first so gradients band into a fine pattern
+*/
+
+use super::PostProcess;
+use crate::framebuffer::Framebuffer;
+use crate::mm::Vec3;
+
+// offsets in 8 bit steps, straight from the ps1 gpu
+const DITHER: [[i32; 4]; 4] = [
+    [-4, 0, -3, 1],
+    [2, -2, 3, -1],
+    [-3, 1, -4, 0],
+    [3, -1, 2, -2],
+];
+
+pub struct Rgb555 {
+    pub dither: bool,
+}
+
+impl Rgb555 {
+    pub fn new(dither: bool) -> Rgb555 {
+        Rgb555 { dither }
+    }
+}
+
+impl Default for Rgb555 {
+    fn default() -> Rgb555 {
+        Rgb555::new(true)
+    }
+}
+
+fn to_5_bits(v: f32, offset: i32) -> f32 {
+    let byte = ((v.clamp(0.0, 1.0) * 255.0) as i32 + offset).clamp(0, 255);
+    let five = byte >> 3;
+    // expand back to 8 bits so white stays white
+    ((five << 3) | (five >> 2)) as f32 / 255.0
+}
+
+impl PostProcess for Rgb555 {
+    fn name(&self) -> &str {
+        "rgb555"
+    }
+
+    fn apply(&mut self, fb: &mut Framebuffer) {
+        for y in 0..fb.height {
+            for x in 0..fb.width {
+                let offset = if self.dither { DITHER[y % 4][x % 4] } else { 0 };
+                let i = fb.index(x, y);
+                let c = fb.color[i];
+                fb.color[i] = Vec3::new(
+                    to_5_bits(c.x, offset),
+                    to_5_bits(c.y, offset),
+                    to_5_bits(c.z, offset),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+/*
+    sharpen
+    unsharp mask with a plus shaped laplacian
+*/
+
+use super::{fetch, PostProcess};
+use crate::framebuffer::Framebuffer;
+
+pub struct Sharpen {
+    pub amount: f32,
+}
+
+impl Sharpen {
+    pub fn new(amount: f32) -> Sharpen {
+        Sharpen { amount }
+    }
+}
+
+impl Default for Sharpen {
+    fn default() -> Sharpen {
+        Sharpen::new(0.5)
+    }
+}
+
+impl PostProcess for Sharpen {
+    fn name(&self) -> &str {
+        "sharpen"
+    }
+
+    fn apply(&mut self, fb: &mut Framebuffer) {
+        let (width, height) = (fb.width, fb.height);
+        let src = fb.color.clone();
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                let c = fetch(&src, width, height, x, y);
+                let neighbors = fetch(&src, width, height, x - 1, y)
+                    + fetch(&src, width, height, x + 1, y)
+                    + fetch(&src, width, height, x, y - 1)
+                    + fetch(&src, width, height, x, y + 1);
+                let i = fb.index(x as usize, y as usize);
+                fb.color[i] = c + (c * 4.0 - neighbors) * self.amount;
+            }
+        }
+    }
+}
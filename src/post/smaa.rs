@@ -0,0 +1,197 @@
+/*
+    smaa
+    a simplified take on smaa 1x, closer to the original mlaa
+    luma edge detection with local contrast adaptation, then every edge is
+    searched along to its ends, and the crossing edges found there give the
+    shape of the real silhouette. blend weights come from that reconstructed
+    line directly instead of the precomputed area texture
+*/
+
+use super::{luma, PostProcess};
+use crate::framebuffer::Framebuffer;
+use crate::mm::Vec3;
+
+pub struct Smaa {
+    // luma difference needed to count as an edge
+    pub threshold: f32,
+    // edges much weaker than a neighboring edge are dropped
+    pub contrast_adaptation: f32,
+    // how many pixels to walk along an edge looking for its end
+    pub max_search: usize,
+}
+
+impl Smaa {
+    pub fn new() -> Smaa {
+        Smaa {
+            threshold: 0.1,
+            contrast_adaptation: 2.0,
+            max_search: 16,
+        }
+    }
+}
+
+impl Default for Smaa {
+    fn default() -> Smaa {
+        Smaa::new()
+    }
+}
+
+// height of the silhouette at an edge end, in pixels across the edge
+// positive leans into the pixel that owns the edge, negative into its neighbor
+fn crossing(neighbor_side: bool, own_side: bool) -> f32 {
+    match (neighbor_side, own_side) {
+        (true, false) => -0.5,
+        (false, true) => 0.5,
+        _ => 0.0,
+    }
+}
+
+// signed coverage of the reconstructed line at position t along an edge
+fn coverage(h1: f32, h2: f32, length: f32, t: f32) -> f32 {
+    match (h1 != 0.0, h2 != 0.0) {
+        (false, false) => 0.0,
+        // l shapes fade from the crossing end to nothing at the other
+        (true, false) => h1 * (1.0 - t / length),
+        (false, true) => h2 * (t / length),
+        // z shapes run straight from one side to the other
+        (true, true) if (h1 > 0.0) != (h2 > 0.0) => h1 + (h2 - h1) * t / length,
+        // u shapes bend back, so each half goes to zero at the middle
+        (true, true) => {
+            let half = length / 2.0;
+            if t < half {
+                h1 * (1.0 - t / half)
+            } else {
+                h2 * ((t - half) / half)
+            }
+        }
+    }
+}
+
+impl PostProcess for Smaa {
+    fn name(&self) -> &str {
+        "smaa"
+    }
+
+    fn apply(&mut self, fb: &mut Framebuffer) {
+        let (width, height) = (fb.width, fb.height);
+        let src = fb.color.clone();
+        let lumas: Vec<f32> = src.iter().map(|&c| luma(c)).collect();
+        let l = |x: usize, y: usize| lumas[y * width + x];
+
+        // pass 1, edges on the left and top side of each pixel
+        let mut left = vec![false; width * height];
+        let mut top = vec![false; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                let c = l(x, y);
+                let dl = if x > 0 { (c - l(x - 1, y)).abs() } else { 0.0 };
+                let dt = if y > 0 { (c - l(x, y - 1)).abs() } else { 0.0 };
+                let is_left = dl > self.threshold;
+                let is_top = dt > self.threshold;
+                if !is_left && !is_top {
+                    continue;
+                }
+
+                let mut max_delta = dl.max(dt);
+                if x + 1 < width {
+                    max_delta = max_delta.max((c - l(x + 1, y)).abs());
+                }
+                if y + 1 < height {
+                    max_delta = max_delta.max((c - l(x, y + 1)).abs());
+                }
+                if x > 1 {
+                    max_delta = max_delta.max((l(x - 1, y) - l(x - 2, y)).abs());
+                }
+                if y > 1 {
+                    max_delta = max_delta.max((l(x, y - 1) - l(x, y - 2)).abs());
+                }
+
+                let i = y * width + x;
+                left[i] = is_left && self.contrast_adaptation * dl >= max_delta;
+                top[i] = is_top && self.contrast_adaptation * dt >= max_delta;
+            }
+        }
+
+        // pass 2, blend weights, accumulated per pixel as a weight and a weighted color
+        let mut weights = vec![0.0f32; width * height];
+        let mut colors = vec![Vec3::zero(); width * height];
+        let mut blend = |target: usize, source: usize, weight: f32| {
+            weights[target] += weight;
+            colors[target] += src[source] * weight;
+        };
+
+        // horizontal edges, between (x, y - 1) and (x, y)
+        for y in 1..height {
+            for x in 0..width {
+                let i = y * width + x;
+                if !top[i] {
+                    continue;
+                }
+                let mut xl = x;
+                while xl > 0 && top[i - (x - xl) - 1] && x - xl < self.max_search {
+                    xl -= 1;
+                }
+                let mut xr = x;
+                while xr + 1 < width && top[i + (xr - x) + 1] && xr - x < self.max_search {
+                    xr += 1;
+                }
+
+                let row = y * width;
+                let above = (y - 1) * width;
+                let h1 = crossing(left[above + xl], left[row + xl]);
+                let h2 = if xr + 1 < width {
+                    crossing(left[above + xr + 1], left[row + xr + 1])
+                } else {
+                    0.0
+                };
+                let a = coverage(h1, h2, (xr - xl + 1) as f32, (x - xl) as f32 + 0.5);
+                if a > 0.0 {
+                    blend(i, above + x, a);
+                } else if a < 0.0 {
+                    blend(above + x, i, -a);
+                }
+            }
+        }
+
+        // vertical edges, between (x - 1, y) and (x, y)
+        for y in 0..height {
+            for x in 1..width {
+                let i = y * width + x;
+                if !left[i] {
+                    continue;
+                }
+                let mut yt = y;
+                while yt > 0 && left[(yt - 1) * width + x] && y - yt < self.max_search {
+                    yt -= 1;
+                }
+                let mut yb = y;
+                while yb + 1 < height && left[(yb + 1) * width + x] && yb - y < self.max_search {
+                    yb += 1;
+                }
+
+                let h1 = crossing(top[yt * width + x - 1], top[yt * width + x]);
+                let h2 = if yb + 1 < height {
+                    crossing(top[(yb + 1) * width + x - 1], top[(yb + 1) * width + x])
+                } else {
+                    0.0
+                };
+                let a = coverage(h1, h2, (yb - yt + 1) as f32, (y - yt) as f32 + 0.5);
+                if a > 0.0 {
+                    blend(i, i - 1, a);
+                } else if a < 0.0 {
+                    blend(i - 1, i, -a);
+                }
+            }
+        }
+
+        // pass 3, neighborhood blending
+        for (i, c) in fb.color.iter_mut().enumerate() {
+            let w = weights[i];
+            if w <= 0.0 {
+                continue;
+            }
+            let amount = w.min(1.0);
+            *c = src[i] * (1.0 - amount) + colors[i] * (amount / w);
+        }
+    }
+}
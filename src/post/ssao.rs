@@ -0,0 +1,200 @@
+/*
+    screen space ambient occlusion
+    samples a hemisphere around each pixel's normal and counts how many of the
+    samples end up behind the depth buffer. a small tiled noise texture rotates
+    the kernel per pixel, then a depth and normal aware blur cleans up the noise
+    expects a perspective camera, set with set_view before the chain runs
+*/
+
+use super::PostProcess;
+use crate::framebuffer::Framebuffer;
+use crate::mm::{Mat4, Vec3};
+use crate::noise::Rng;
+
+const NOISE_SIZE: usize = 4;
+
+pub struct Ssao {
+    // world space radius of the hemisphere
+    pub radius: f32,
+    // world space distance a sample has to be hidden by before it counts
+    pub bias: f32,
+    // exponent on the final occlusion, higher is darker
+    pub intensity: f32,
+    pub blur_radius: usize,
+    kernel: Vec<Vec3>,
+    noise: Vec<Vec3>,
+    view_proj: Mat4,
+    inv_view_proj: Mat4,
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+impl Ssao {
+    pub fn new(samples: usize) -> Ssao {
+        let mut rng = Rng::new(3);
+        // points in the +z hemisphere, bunched up towards the middle
+        let kernel = (0..samples)
+            .map(|i| {
+                let v = Vec3::new(
+                    rng.next_f32() * 2.0 - 1.0,
+                    rng.next_f32() * 2.0 - 1.0,
+                    rng.next_f32(),
+                )
+                .normalize();
+                let t = i as f32 / samples as f32;
+                v * (rng.next_f32() * (0.1 + 0.9 * t * t))
+            })
+            .collect();
+        // random rotations around the normal, tiled across the screen
+        let noise = (0..NOISE_SIZE * NOISE_SIZE)
+            .map(|_| {
+                let a = rng.next_f32() * std::f32::consts::TAU;
+                Vec3::new(a.cos(), a.sin(), 0.0)
+            })
+            .collect();
+        Ssao {
+            radius: 1.0,
+            bias: 0.05,
+            intensity: 1.5,
+            blur_radius: 2,
+            kernel,
+            noise,
+            view_proj: Mat4::identity(),
+            inv_view_proj: Mat4::identity(),
+        }
+    }
+
+    // world position of every pixel with something drawn in it
+    fn positions(&self, fb: &Framebuffer) -> Vec<Option<Vec3>> {
+        let mut positions = vec![None; fb.width * fb.height];
+        for y in 0..fb.height {
+            for x in 0..fb.width {
+                let i = fb.index(x, y);
+                let depth = fb.depth[fb.sample_index(i, 0)];
+                if depth >= 1.0 || fb.normal[i].dot(&fb.normal[i]) == 0.0 {
+                    continue;
+                }
+                let ndc = Vec3::new(
+                    (x as f32 + 0.5) / fb.width as f32 * 2.0 - 1.0,
+                    1.0 - (y as f32 + 0.5) / fb.height as f32 * 2.0,
+                    depth * 2.0 - 1.0,
+                );
+                let p = self.inv_view_proj * ndc.extend(1.0);
+                positions[i] = Some(p.xyz() / p.w);
+            }
+        }
+        positions
+    }
+
+    // fraction of the hemisphere that is open, 1 is fully unoccluded
+    fn occlusion(&self, fb: &Framebuffer, positions: &[Option<Vec3>], x: usize, y: usize) -> f32 {
+        let i = fb.index(x, y);
+        let p = match positions[i] {
+            Some(p) => p,
+            None => return 1.0,
+        };
+        let n = fb.normal[i].normalize();
+        let r = self.noise[(y % NOISE_SIZE) * NOISE_SIZE + x % NOISE_SIZE];
+        // gram-schmidt the noise vector into a tangent frame around the normal
+        let mut t = r - n * r.dot(&n);
+        if t.dot(&t) < 1e-6 {
+            t = n.cross(&Vec3::new(0.0, 0.0, 1.0));
+            if t.dot(&t) < 1e-6 {
+                t = n.cross(&Vec3::new(1.0, 0.0, 0.0));
+            }
+        }
+        let t = t.normalize();
+        let b = n.cross(&t);
+
+        let mut occluded = 0.0;
+        for k in &self.kernel {
+            let s = p + (t * k.x + b * k.y + n * k.z) * self.radius;
+            let clip = self.view_proj * s.extend(1.0);
+            if clip.w <= 0.0 {
+                continue;
+            }
+            let sx = ((clip.x / clip.w * 0.5 + 0.5) * fb.width as f32).floor();
+            let sy = ((0.5 - clip.y / clip.w * 0.5) * fb.height as f32).floor();
+            if sx < 0.0 || sy < 0.0 || sx >= fb.width as f32 || sy >= fb.height as f32 {
+                continue;
+            }
+            let q = match positions[fb.index(sx as usize, sy as usize)] {
+                Some(q) => q,
+                None => continue,
+            };
+            // w is the distance along the view axis for a perspective camera
+            let scene_w = (self.view_proj * q.extend(1.0)).w;
+            if scene_w < clip.w - self.bias {
+                // fade out occluders far outside the radius, they are probably a different object
+                occluded += smoothstep(0.0, 1.0, self.radius / (p - q).length());
+            }
+        }
+        1.0 - occluded / self.kernel.len() as f32
+    }
+}
+
+impl Default for Ssao {
+    fn default() -> Ssao {
+        Ssao::new(16)
+    }
+}
+
+impl PostProcess for Ssao {
+    fn name(&self) -> &str {
+        "ssao"
+    }
+
+    fn set_view(&mut self, view_proj: &Mat4) {
+        self.view_proj = *view_proj;
+        self.inv_view_proj = view_proj.inverse().unwrap_or_else(Mat4::identity);
+    }
+
+    fn apply(&mut self, fb: &mut Framebuffer) {
+        let positions = self.positions(fb);
+        let mut ao = vec![1.0; fb.width * fb.height];
+        for y in 0..fb.height {
+            for x in 0..fb.width {
+                ao[fb.index(x, y)] = self.occlusion(fb, &positions, x, y);
+            }
+        }
+
+        // bilateral blur, only averages neighbors on the same surface
+        let r = self.blur_radius as i32;
+        for y in 0..fb.height as i32 {
+            for x in 0..fb.width as i32 {
+                let i = fb.index(x as usize, y as usize);
+                let p = match positions[i] {
+                    Some(p) => p,
+                    None => continue,
+                };
+                let n = fb.normal[i].normalize();
+                let mut sum = 0.0;
+                let mut weight = 0.0;
+                for dy in -r..=r {
+                    for dx in -r..=r {
+                        let (nx, ny) = (x + dx, y + dy);
+                        if nx < 0 || ny < 0 || nx >= fb.width as i32 || ny >= fb.height as i32 {
+                            continue;
+                        }
+                        let j = fb.index(nx as usize, ny as usize);
+                        let q = match positions[j] {
+                            Some(q) => q,
+                            None => continue,
+                        };
+                        if (p - q).length() > self.radius || n.dot(&fb.normal[j].normalize()) < 0.8
+                        {
+                            continue;
+                        }
+                        sum += ao[j];
+                        weight += 1.0;
+                    }
+                }
+                let occlusion: f32 = if weight > 0.0 { sum / weight } else { ao[i] };
+                fb.color[i] *= occlusion.max(0.0).powf(self.intensity);
+            }
+        }
+    }
+}
@@ -0,0 +1,118 @@
+/*
+    n64 video interface filter
+    the vi smoothed the picture on its way out to the tv:
+    edge pixels with partial coverage get blended towards a background color
+    guessed from their neighbors, a divot filter takes a 3 wide median across
+    those edges to clean up the leftover notches, and the horizontal
+    resampling blur gives the final soft look
+*/
+
+use super::{fetch, PostProcess};
+use crate::framebuffer::{Framebuffer, FULL_COVERAGE};
+use crate::mm::Vec3;
+
+pub struct ViFilter {
+    pub antialias: bool,
+    pub divot: bool,
+    pub blur: bool,
+}
+
+impl ViFilter {
+    pub fn new() -> ViFilter {
+        ViFilter {
+            antialias: true,
+            divot: true,
+            blur: true,
+        }
+    }
+}
+
+impl Default for ViFilter {
+    fn default() -> ViFilter {
+        ViFilter::new()
+    }
+}
+
+// second smallest plus second largest, what the vi used so a single odd neighbor is ignored
+fn penultimate_sum(mut values: [f32; 8]) -> f32 {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    values[1] + values[6]
+}
+
+fn median3(a: f32, b: f32, c: f32) -> f32 {
+    a.max(b).min(a.min(b).max(c))
+}
+
+impl PostProcess for ViFilter {
+    fn name(&self) -> &str {
+        "vi"
+    }
+
+    fn apply(&mut self, fb: &mut Framebuffer) {
+        let (width, height) = (fb.width, fb.height);
+        let partial =
+            |fb: &Framebuffer, x: usize, y: usize| fb.coverage[fb.index(x, y)] < FULL_COVERAGE;
+
+        if self.antialias {
+            let src = fb.color.clone();
+            for y in 0..height {
+                for x in 0..width {
+                    if !partial(fb, x, y) {
+                        continue;
+                    }
+                    let mut neighbors = [Vec3::zero(); 8];
+                    let mut n = 0;
+                    for dy in -1..=1 {
+                        for dx in -1..=1 {
+                            if dx != 0 || dy != 0 {
+                                neighbors[n] =
+                                    fetch(&src, width, height, x as i32 + dx, y as i32 + dy);
+                                n += 1;
+                            }
+                        }
+                    }
+                    let i = fb.index(x, y);
+                    let c = src[i];
+                    let background = Vec3::new(
+                        penultimate_sum(neighbors.map(|v| v.x)),
+                        penultimate_sum(neighbors.map(|v| v.y)),
+                        penultimate_sum(neighbors.map(|v| v.z)),
+                    ) - c;
+                    let uncovered = 1.0 - fb.coverage[i] as f32 / FULL_COVERAGE as f32;
+                    fb.color[i] = c + (background - c) * uncovered;
+                }
+            }
+        }
+
+        if self.divot {
+            let src = fb.color.clone();
+            for y in 0..height {
+                for x in 1..width.saturating_sub(1) {
+                    if !(partial(fb, x - 1, y) || partial(fb, x, y) || partial(fb, x + 1, y)) {
+                        continue;
+                    }
+                    let i = fb.index(x, y);
+                    let (l, c, r) = (src[i - 1], src[i], src[i + 1]);
+                    fb.color[i] = Vec3::new(
+                        median3(l.x, c.x, r.x),
+                        median3(l.y, c.y, r.y),
+                        median3(l.z, c.z, r.z),
+                    );
+                }
+            }
+        }
+
+        if self.blur {
+            let src = fb.color.clone();
+            for y in 0..height as i32 {
+                for x in 0..width as i32 {
+                    let i = fb.index(x as usize, y as usize);
+                    fb.color[i] = (fetch(&src, width, height, x - 1, y)
+                        + src[i] * 2.0
+                        + fetch(&src, width, height, x + 1, y))
+                        / 4.0;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,58 @@
+/*
+    vignette
+    darkens the image towards the corners
+*/
+
+use super::PostProcess;
+use crate::framebuffer::Framebuffer;
+
+pub struct Vignette {
+    // how dark the corners get, 0 to 1
+    pub intensity: f32,
+    // distance from the center where darkening starts, the half diagonal is about 0.7
+    pub radius: f32,
+    // width of the falloff past the radius
+    pub softness: f32,
+}
+
+impl Vignette {
+    pub fn new(intensity: f32, radius: f32, softness: f32) -> Vignette {
+        Vignette {
+            intensity,
+            radius,
+            softness,
+        }
+    }
+}
+
+impl Default for Vignette {
+    fn default() -> Vignette {
+        Vignette::new(0.6, 0.4, 0.35)
+    }
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+impl PostProcess for Vignette {
+    fn name(&self) -> &str {
+        "vignette"
+    }
+
+    fn apply(&mut self, fb: &mut Framebuffer) {
+        let (width, height) = (fb.width as f32, fb.height as f32);
+        for y in 0..fb.height {
+            for x in 0..fb.width {
+                // normalized by height so the falloff stays round on wide screens
+                let dx = (x as f32 + 0.5 - width / 2.0) / height;
+                let dy = (y as f32 + 0.5 - height / 2.0) / height;
+                let distance = (dx * dx + dy * dy).sqrt();
+                let dark = smoothstep(self.radius, self.radius + self.softness, distance);
+                let i = fb.index(x, y);
+                fb.color[i] *= 1.0 - self.intensity * dark;
+            }
+        }
+    }
+}
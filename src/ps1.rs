@@ -0,0 +1,77 @@
+/*
+    playstation style rendering
+    the ps1 had no depth buffer, games bucketed primitives by depth into an
+    ordering table and drew it back to front, painter style. combine this with
+    RenderState::ps1() and the rgb555 post effect for the full look
+*/
+
+use crate::framebuffer::Framebuffer;
+use crate::mm::Vec3;
+use crate::raster::{draw_triangle, Fragment, Vertex};
+use crate::state::RenderState;
+
+// depth sorted buckets, everything in one bucket draws in insertion order
+pub struct OrderingTable<T> {
+    buckets: Vec<Vec<T>>,
+}
+
+impl<T> OrderingTable<T> {
+    pub fn new(size: usize) -> OrderingTable<T> {
+        OrderingTable {
+            buckets: (0..size).map(|_| Vec::new()).collect(),
+        }
+    }
+
+    // depth runs 0 (near) to 1 (far), anything outside is clamped to the ends
+    pub fn insert(&mut self, depth: f32, item: T) {
+        let last = self.buckets.len() - 1;
+        let slot = ((depth.clamp(0.0, 1.0) * last as f32) as usize).min(last);
+        self.buckets[slot].push(item);
+    }
+
+    pub fn clear(&mut self) {
+        for bucket in self.buckets.iter_mut() {
+            bucket.clear();
+        }
+    }
+
+    // far buckets first
+    pub fn drain_back_to_front(&mut self) -> Vec<T> {
+        let mut out = Vec::new();
+        for bucket in self.buckets.iter_mut().rev() {
+            out.append(bucket);
+        }
+        out
+    }
+}
+
+// average depth of a clip space triangle in the 0..1 range, like gte avsz3
+fn average_depth(tri: &[Vertex; 3]) -> f32 {
+    let mut sum = 0.0;
+    for v in tri {
+        let w = v.position.w.max(1e-5);
+        sum += v.position.z / w * 0.5 + 0.5;
+    }
+    sum / 3.0
+}
+
+// draw an indexed triangle list sorted through an ordering table instead of a depth buffer
+pub fn draw_ordered<F>(
+    fb: &mut Framebuffer,
+    state: &RenderState,
+    vertices: &[Vertex],
+    indices: &[[usize; 3]],
+    table_size: usize,
+    shade: &mut F,
+) where
+    F: FnMut(&Fragment) -> Option<Vec3>,
+{
+    let mut table = OrderingTable::new(table_size);
+    for tri in indices {
+        let tri = [vertices[tri[0]], vertices[tri[1]], vertices[tri[2]]];
+        table.insert(average_depth(&tri), tri);
+    }
+    for tri in table.drain_back_to_front() {
+        draw_triangle(fb, state, &tri, shade);
+    }
+}
@@ -0,0 +1,492 @@
+/*
+    triangle rasterizer
+    clips against the near plane, projects into the framebuffer and fills
+    with perspective correct varyings, running stencil and depth tests per sample
+*/
+
+use crate::framebuffer::{sample_positions, Antialias, Framebuffer, FULL_COVERAGE};
+use crate::mm::{Vec2, Vec3, Vec4};
+use crate::state::{Cull, RenderState};
+
+// attributes interpolated across a triangle
+#[derive(Debug, Copy, Clone)]
+pub struct Varying {
+    pub color: Vec3,
+    pub normal: Vec3,
+    pub world: Vec3,
+    pub uv: Vec2,
+    // xyz tangent and bitangent sign in w, zero when the mesh has none
+    pub tangent: Vec4,
+}
+
+impl Varying {
+    pub fn new(color: Vec3) -> Varying {
+        Varying {
+            color,
+            normal: Vec3::zero(),
+            world: Vec3::zero(),
+            uv: Vec2::zero(),
+            tangent: Vec4::zero(),
+        }
+    }
+
+    pub fn lerp(&self, other: &Varying, t: f32) -> Varying {
+        Varying {
+            color: self.color.lerp(&other.color, t),
+            normal: self.normal.lerp(&other.normal, t),
+            world: self.world.lerp(&other.world, t),
+            uv: self.uv.lerp(&other.uv, t),
+            tangent: self.tangent.lerp(&other.tangent, t),
+        }
+    }
+
+    // weighted sum of three varyings, weights are expected to add up to one
+    pub fn blend(a: &Varying, b: &Varying, c: &Varying, w: [f32; 3]) -> Varying {
+        Varying {
+            color: a.color * w[0] + b.color * w[1] + c.color * w[2],
+            normal: a.normal * w[0] + b.normal * w[1] + c.normal * w[2],
+            world: a.world * w[0] + b.world * w[1] + c.world * w[2],
+            uv: a.uv * w[0] + b.uv * w[1] + c.uv * w[2],
+            tangent: a.tangent * w[0] + b.tangent * w[1] + c.tangent * w[2],
+        }
+    }
+}
+
+// a vertex coming out of the vertex stage, position is in clip space
+#[derive(Debug, Copy, Clone)]
+pub struct Vertex {
+    pub position: Vec4,
+    pub varying: Varying,
+}
+
+// what the fragment shader gets to see for each covered pixel
+pub struct Fragment {
+    pub x: usize,
+    pub y: usize,
+    pub depth: f32,
+    pub front_facing: bool,
+    pub varying: Varying,
+}
+
+// vertex after the perspective divide and viewport transform
+#[derive(Copy, Clone)]
+struct ScreenVertex {
+    x: f32,
+    y: f32,
+    z: f32,
+    inv_w: f32,
+    varying: Varying,
+}
+
+// sutherland-hodgman against the near plane (z >= -w)
+// returns a convex polygon with 0, 3 or 4 vertices
+fn clip_near(tri: &[Vertex; 3]) -> Vec<Vertex> {
+    let mut out = Vec::with_capacity(4);
+    for i in 0..3 {
+        let a = &tri[i];
+        let b = &tri[(i + 1) % 3];
+        let da = a.position.z + a.position.w;
+        let db = b.position.z + b.position.w;
+        if da >= 0.0 {
+            out.push(*a);
+        }
+        if (da >= 0.0) != (db >= 0.0) {
+            let t = da / (da - db);
+            out.push(Vertex {
+                position: a.position.lerp(&b.position, t),
+                varying: a.varying.lerp(&b.varying, t),
+            });
+        }
+    }
+    out
+}
+
+fn to_screen(fb: &Framebuffer, state: &RenderState, v: &Vertex) -> ScreenVertex {
+    let inv_w = 1.0 / v.position.w;
+    let mut x = (v.position.x * inv_w * 0.5 + 0.5) * fb.width as f32;
+    let mut y = (0.5 - v.position.y * inv_w * 0.5) * fb.height as f32;
+    if state.snap_vertices {
+        x = x.round();
+        y = y.round();
+    }
+    ScreenVertex {
+        x,
+        y,
+        z: v.position.z * inv_w * 0.5 + 0.5,
+        inv_w,
+        varying: v.varying,
+    }
+}
+
+fn edge(a: &ScreenVertex, b: &ScreenVertex, px: f32, py: f32) -> f32 {
+    (b.x - a.x) * (py - a.y) - (b.y - a.y) * (px - a.x)
+}
+
+// top-left fill rule, so pixels on shared edges are only drawn once
+fn is_top_left(a: &ScreenVertex, b: &ScreenVertex) -> bool {
+    (a.y == b.y && b.x > a.x) || b.y < a.y
+}
+
+fn covered(w: f32, top_left: bool) -> bool {
+    w > 0.0 || (w == 0.0 && top_left)
+}
+
+// draw one clip space triangle
+// the shader returns the fragment color, or None to discard it
+pub fn draw_triangle<F>(fb: &mut Framebuffer, state: &RenderState, tri: &[Vertex; 3], shade: &mut F)
+where
+    F: FnMut(&Fragment) -> Option<Vec3>,
+{
+    let poly = clip_near(tri);
+    if poly.len() < 3 {
+        return;
+    }
+    let screen: Vec<ScreenVertex> = poly.iter().map(|v| to_screen(fb, state, v)).collect();
+    for i in 1..screen.len() - 1 {
+        fill(fb, state, [screen[0], screen[i], screen[i + 1]], shade);
+    }
+}
+
+// one pixel of a line or point, run through the stencil and depth tests per sample like
+// fill does, shaded at most once and written to every sample that passed.
+// z is unbiased, range checked before the state's depth bias goes on like triangles
+fn plot<F>(fb: &mut Framebuffer, state: &RenderState, x: usize, y: usize, z: f32, frag: F) -> bool
+where
+    F: FnOnce(f32) -> Option<Vec3>,
+{
+    if !(0.0..=1.0).contains(&z) {
+        return false;
+    }
+    let z = z + state.depth_bias;
+    let pixel = fb.index(x, y);
+    let stencil = &state.stencil;
+    let mut passed = Vec::with_capacity(fb.samples());
+    for s in 0..fb.samples() {
+        let i = fb.sample_index(pixel, s);
+        if !stencil.test(fb.stencil[i]) {
+            if stencil.enabled {
+                fb.stencil[i] = stencil.apply(stencil.fail, fb.stencil[i]);
+            }
+            continue;
+        }
+        if state.depth_test && !state.depth_func.test(z, fb.depth[i]) {
+            if stencil.enabled {
+                fb.stencil[i] = stencil.apply(stencil.depth_fail, fb.stencil[i]);
+            }
+            continue;
+        }
+        passed.push(s);
+    }
+    if passed.is_empty() {
+        return false;
+    }
+    let color = match frag(z) {
+        Some(color) => color,
+        None => return false,
+    };
+    for s in passed {
+        let i = fb.sample_index(pixel, s);
+        if stencil.enabled {
+            fb.stencil[i] = stencil.apply(stencil.pass, fb.stencil[i]);
+        }
+        if state.depth_test && state.depth_write {
+            fb.depth[i] = z;
+        }
+        if state.color_write {
+            fb.write_color(pixel, s, color);
+        }
+    }
+    true
+}
+
+// draw a clip space line one pixel wide, stepping along its longer screen axis
+// it is clipped to the near plane and the screen, then depth and varyings are
+// interpolated like a triangle edge would be
+pub fn draw_line<F>(fb: &mut Framebuffer, state: &RenderState, line: &[Vertex; 2], shade: &mut F)
+where
+    F: FnMut(&Fragment) -> Option<Vec3>,
+{
+    let [mut a, mut b] = *line;
+    let da = a.position.z + a.position.w;
+    let db = b.position.z + b.position.w;
+    if da < 0.0 && db < 0.0 {
+        return;
+    }
+    if da < 0.0 {
+        let t = da / (da - db);
+        a = Vertex {
+            position: a.position.lerp(&b.position, t),
+            varying: a.varying.lerp(&b.varying, t),
+        };
+    } else if db < 0.0 {
+        let t = db / (db - da);
+        b = Vertex {
+            position: b.position.lerp(&a.position, t),
+            varying: b.varying.lerp(&a.varying, t),
+        };
+    }
+    let sa = to_screen(fb, state, &a);
+    let sb = to_screen(fb, state, &b);
+
+    // liang-barsky against the screen so far off endpoints don't cost any steps
+    let (dx, dy) = (sb.x - sa.x, sb.y - sa.y);
+    let (mut t0, mut t1) = (0.0f32, 1.0f32);
+    for (p, q) in [
+        (-dx, sa.x),
+        (dx, fb.width as f32 - sa.x),
+        (-dy, sa.y),
+        (dy, fb.height as f32 - sa.y),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return;
+            }
+        } else {
+            let r = q / p;
+            if p < 0.0 {
+                t0 = t0.max(r);
+            } else {
+                t1 = t1.min(r);
+            }
+        }
+    }
+    if t0 > t1 {
+        return;
+    }
+
+    let steps = ((dx.abs().max(dy.abs())) * (t1 - t0)).ceil().max(1.0) as usize;
+    for i in 0..=steps {
+        let l = t0 + (t1 - t0) * i as f32 / steps as f32;
+        let px = sa.x + dx * l;
+        let py = sa.y + dy * l;
+        if px < 0.0 || py < 0.0 || px >= fb.width as f32 || py >= fb.height as f32 {
+            continue;
+        }
+        let (x, y) = (px as usize, py as usize);
+        let z = sa.z + (sb.z - sa.z) * l;
+        plot(fb, state, x, y, z, |depth| {
+            let t = if state.perspective_correct {
+                let p0 = (1.0 - l) * sa.inv_w;
+                let p1 = l * sb.inv_w;
+                p1 / (p0 + p1)
+            } else {
+                l
+            };
+            shade(&Fragment {
+                x,
+                y,
+                depth,
+                front_facing: true,
+                varying: sa.varying.lerp(&sb.varying, t),
+            })
+        });
+    }
+}
+
+// draw a clip space point as a size by size pixel square, all at the point's depth
+pub fn draw_point<F>(
+    fb: &mut Framebuffer,
+    state: &RenderState,
+    point: &Vertex,
+    size: f32,
+    shade: &mut F,
+) where
+    F: FnMut(&Fragment) -> Option<Vec3>,
+{
+    if point.position.z + point.position.w < 0.0 {
+        return;
+    }
+    let sv = to_screen(fb, state, point);
+    let half = size.max(1.0) * 0.5;
+    let min_x = (sv.x - half).round().max(0.0) as usize;
+    let min_y = (sv.y - half).round().max(0.0) as usize;
+    let max_x = ((sv.x + half).round().max(0.0) as usize).min(fb.width);
+    let max_y = ((sv.y + half).round().max(0.0) as usize).min(fb.height);
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            plot(fb, state, x, y, sv.z, |depth| {
+                shade(&Fragment {
+                    x,
+                    y,
+                    depth,
+                    front_facing: true,
+                    varying: sv.varying,
+                })
+            });
+        }
+    }
+}
+
+// draw an indexed triangle list
+pub fn draw_indexed<F>(
+    fb: &mut Framebuffer,
+    state: &RenderState,
+    vertices: &[Vertex],
+    indices: &[[usize; 3]],
+    shade: &mut F,
+) where
+    F: FnMut(&Fragment) -> Option<Vec3>,
+{
+    for tri in indices {
+        let tri = [vertices[tri[0]], vertices[tri[1]], vertices[tri[2]]];
+        draw_triangle(fb, state, &tri, shade);
+    }
+}
+
+fn fill<F>(fb: &mut Framebuffer, state: &RenderState, tri: [ScreenVertex; 3], shade: &mut F)
+where
+    F: FnMut(&Fragment) -> Option<Vec3>,
+{
+    let [v0, mut v1, mut v2] = tri;
+    let mut area = edge(&v0, &v1, v2.x, v2.y);
+    if area == 0.0 {
+        return;
+    }
+
+    // y points down on screen, so counter clockwise in ndc comes out negative here
+    let front_facing = area < 0.0;
+    match state.cull {
+        Cull::Back if !front_facing => return,
+        Cull::Front if front_facing => return,
+        _ => {}
+    }
+    if area < 0.0 {
+        std::mem::swap(&mut v1, &mut v2);
+        area = -area;
+    }
+
+    let min_x = v0.x.min(v1.x).min(v2.x).floor().max(0.0) as usize;
+    let min_y = v0.y.min(v1.y).min(v2.y).floor().max(0.0) as usize;
+    let max_x = v0.x.max(v1.x).max(v2.x).ceil().min(fb.width as f32) as usize;
+    let max_y = v0.y.max(v1.y).max(v2.y).ceil().min(fb.height as f32) as usize;
+
+    // polygon offset from how fast depth changes across the screen
+    let depth_offset = if state.depth_bias != 0.0 || state.slope_bias != 0.0 {
+        let dzdx = ((v1.y - v2.y) * v0.z + (v2.y - v0.y) * v1.z + (v0.y - v1.y) * v2.z) / area;
+        let dzdy = ((v2.x - v1.x) * v0.z + (v0.x - v2.x) * v1.z + (v1.x - v0.x) * v2.z) / area;
+        state.depth_bias + state.slope_bias * dzdx.abs().max(dzdy.abs())
+    } else {
+        0.0
+    };
+
+    let tl0 = is_top_left(&v1, &v2);
+    let tl1 = is_top_left(&v2, &v0);
+    let tl2 = is_top_left(&v0, &v1);
+
+    let samples = fb.samples();
+    let positions = sample_positions(samples);
+    let per_sample_shading = matches!(fb.antialias, Antialias::Ssaa(_));
+    let track_coverage = state.coverage && samples == 1;
+    let coverage_positions = sample_positions(FULL_COVERAGE as usize);
+
+    // barycentric weights of a screen point, not perspective corrected
+    let weights = |px: f32, py: f32| {
+        [
+            edge(&v1, &v2, px, py) / area,
+            edge(&v2, &v0, px, py) / area,
+            edge(&v0, &v1, px, py) / area,
+        ]
+    };
+
+    let fragment = |x: usize, y: usize, l: [f32; 3]| {
+        let weights = if state.perspective_correct {
+            let p0 = l[0] * v0.inv_w;
+            let p1 = l[1] * v1.inv_w;
+            let p2 = l[2] * v2.inv_w;
+            let sum = p0 + p1 + p2;
+            [p0 / sum, p1 / sum, p2 / sum]
+        } else {
+            l
+        };
+        Fragment {
+            x,
+            y,
+            depth: l[0] * v0.z + l[1] * v1.z + l[2] * v2.z,
+            front_facing,
+            varying: Varying::blend(&v0.varying, &v1.varying, &v2.varying, weights),
+        }
+    };
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let pixel = fb.index(x, y);
+            // msaa shades once per pixel at its center, the first time a sample needs it
+            let mut pixel_color: Option<(Option<Vec3>, Vec3)> = None;
+
+            for (s, &(ox, oy)) in positions.iter().enumerate() {
+                let px = x as f32 + 0.5 + ox;
+                let py = y as f32 + 0.5 + oy;
+                let w0 = edge(&v1, &v2, px, py);
+                let w1 = edge(&v2, &v0, px, py);
+                let w2 = edge(&v0, &v1, px, py);
+                if !(covered(w0, tl0) && covered(w1, tl1) && covered(w2, tl2)) {
+                    continue;
+                }
+                let l = [w0 / area, w1 / area, w2 / area];
+
+                // depth is affine in screen space, no perspective correction needed
+                let z = l[0] * v0.z + l[1] * v1.z + l[2] * v2.z;
+                if !(0.0..=1.0).contains(&z) {
+                    continue;
+                }
+                let z = z + depth_offset;
+
+                let i = fb.sample_index(pixel, s);
+                let stencil = &state.stencil;
+                if !stencil.test(fb.stencil[i]) {
+                    if stencil.enabled {
+                        fb.stencil[i] = stencil.apply(stencil.fail, fb.stencil[i]);
+                    }
+                    continue;
+                }
+                if state.depth_test && !state.depth_func.test(z, fb.depth[i]) {
+                    if stencil.enabled {
+                        fb.stencil[i] = stencil.apply(stencil.depth_fail, fb.stencil[i]);
+                    }
+                    continue;
+                }
+
+                let (color, normal) = if per_sample_shading || samples == 1 {
+                    let frag = fragment(x, y, l);
+                    (shade(&frag), frag.varying.normal)
+                } else {
+                    match pixel_color {
+                        Some(shaded) => shaded,
+                        None => {
+                            let frag = fragment(x, y, weights(x as f32 + 0.5, y as f32 + 0.5));
+                            let shaded = (shade(&frag), frag.varying.normal);
+                            pixel_color = Some(shaded);
+                            shaded
+                        }
+                    }
+                };
+
+                if let Some(color) = color {
+                    if stencil.enabled {
+                        fb.stencil[i] = stencil.apply(stencil.pass, fb.stencil[i]);
+                    }
+                    if state.depth_test && state.depth_write {
+                        fb.depth[i] = z;
+                    }
+                    if state.color_write {
+                        fb.write_color(pixel, s, color);
+                        fb.normal[pixel] = normal;
+                        if track_coverage {
+                            let count = coverage_positions
+                                .iter()
+                                .filter(|&&(cx, cy)| {
+                                    let px = x as f32 + 0.5 + cx;
+                                    let py = y as f32 + 0.5 + cy;
+                                    covered(edge(&v1, &v2, px, py), tl0)
+                                        && covered(edge(&v2, &v0, px, py), tl1)
+                                        && covered(edge(&v0, &v1, px, py), tl2)
+                                })
+                                .count();
+                            fb.coverage[pixel] = count as u8;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,310 @@
+/*
+    scene graph
+    nodes with a local translation, rotation and scale hang off each other,
+    world matrices are cached and only rebuilt under nodes that changed
+    nodes carry attachments, a mesh index into the caller's list, a light or a camera
+*/
+
+use crate::light::{Light, LightKind};
+use crate::mm::{Mat4, Quat, Vec3};
+
+#[derive(Debug, Copy, Clone)]
+pub struct Transform {
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Transform {
+    pub fn new(position: Vec3, rotation: Quat, scale: Vec3) -> Transform {
+        Transform {
+            position,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn identity() -> Transform {
+        Transform::new(Vec3::zero(), Quat::identity(), Vec3::one())
+    }
+
+    pub fn from_position(position: Vec3) -> Transform {
+        Transform {
+            position,
+            ..Transform::identity()
+        }
+    }
+
+    // scale first, then rotate, then move
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::translation(self.position) * self.rotation.to_mat4() * Mat4::scale(self.scale)
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform::identity()
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum Attachment {
+    // index into whatever mesh list the scene is drawn with
+    Mesh(usize),
+    // position and direction are in the node's space
+    Light(Light),
+    // the node's world matrix is where the camera sits, looking down its -z
+    Camera,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct NodeId(pub usize);
+
+pub struct Node {
+    pub name: String,
+    pub attachments: Vec<Attachment>,
+    transform: Transform,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    world: Mat4,
+    dirty: bool,
+}
+
+impl Node {
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    // as of the last Scene::update
+    pub fn world(&self) -> Mat4 {
+        self.world
+    }
+}
+
+pub struct Scene {
+    nodes: Vec<Node>,
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene { nodes: Vec::new() }
+    }
+
+    pub fn add(&mut self, name: &str, transform: Transform, parent: Option<NodeId>) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Node {
+            name: name.to_string(),
+            attachments: Vec::new(),
+            transform,
+            parent,
+            children: Vec::new(),
+            world: Mat4::identity(),
+            dirty: true,
+        });
+        if let Some(parent) = parent {
+            self.nodes[parent.0].children.push(id);
+        }
+        id
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+        self.nodes.iter().enumerate().map(|(i, n)| (NodeId(i), n))
+    }
+
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes.iter().position(|n| n.name == name).map(NodeId)
+    }
+
+    pub fn attach(&mut self, id: NodeId, attachment: Attachment) {
+        self.nodes[id.0].attachments.push(attachment);
+    }
+
+    // moves a node and everything under it, refuses to make a loop
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) -> Result<(), String> {
+        let mut up = parent;
+        while let Some(p) = up {
+            if p == id {
+                return Err(format!(
+                    "{} would end up as its own ancestor",
+                    self.nodes[id.0].name
+                ));
+            }
+            up = self.nodes[p.0].parent;
+        }
+        if let Some(old) = self.nodes[id.0].parent {
+            self.nodes[old.0].children.retain(|&c| c != id);
+        }
+        if let Some(new) = parent {
+            self.nodes[new.0].children.push(id);
+        }
+        self.nodes[id.0].parent = parent;
+        self.mark_dirty(id);
+        Ok(())
+    }
+
+    pub fn set_transform(&mut self, id: NodeId, transform: Transform) {
+        self.nodes[id.0].transform = transform;
+        self.mark_dirty(id);
+    }
+
+    pub fn set_position(&mut self, id: NodeId, position: Vec3) {
+        self.nodes[id.0].transform.position = position;
+        self.mark_dirty(id);
+    }
+
+    pub fn set_rotation(&mut self, id: NodeId, rotation: Quat) {
+        self.nodes[id.0].transform.rotation = rotation;
+        self.mark_dirty(id);
+    }
+
+    pub fn set_scale(&mut self, id: NodeId, scale: Vec3) {
+        self.nodes[id.0].transform.scale = scale;
+        self.mark_dirty(id);
+    }
+
+    // a changed node invalidates its whole subtree. a node that is already dirty
+    // has a dirty subtree too, so the walk can stop there
+    fn mark_dirty(&mut self, id: NodeId) {
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let node = &mut self.nodes[id.0];
+            if node.dirty {
+                continue;
+            }
+            node.dirty = true;
+            stack.extend(node.children.iter().copied());
+        }
+    }
+
+    // rebuilds the world matrices of dirty nodes, parents before children
+    pub fn update(&mut self) {
+        let mut stack: Vec<(NodeId, Mat4)> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| n.parent.is_none())
+            .map(|(i, _)| (NodeId(i), Mat4::identity()))
+            .collect();
+        while let Some((id, parent_world)) = stack.pop() {
+            let node = &mut self.nodes[id.0];
+            if node.dirty {
+                node.world = parent_world * node.transform.matrix();
+                node.dirty = false;
+            }
+            let world = node.world;
+            stack.extend(node.children.iter().map(|&c| (c, world)));
+        }
+    }
+
+    pub fn world(&self, id: NodeId) -> Mat4 {
+        self.nodes[id.0].world
+    }
+
+    // the local rotations from the root down, leaving scale out of it, so
+    // directions like rotation axes stay unskewed under non uniform scale
+    pub fn world_rotation(&self, id: NodeId) -> Quat {
+        let mut rotation = Quat::identity();
+        let mut node = Some(id);
+        while let Some(id) = node {
+            rotation = self.nodes[id.0].transform.rotation * rotation;
+            node = self.nodes[id.0].parent;
+        }
+        rotation
+    }
+
+    // every mesh attachment with the world matrix to draw it with
+    pub fn meshes(&self) -> Vec<(usize, Mat4)> {
+        let mut out = Vec::new();
+        for node in &self.nodes {
+            for attachment in &node.attachments {
+                if let Attachment::Mesh(mesh) = attachment {
+                    out.push((*mesh, node.world));
+                }
+            }
+        }
+        out
+    }
+
+    // the node each mesh attachment hangs off, in the same order as meshes
+    pub fn mesh_nodes(&self) -> Vec<NodeId> {
+        let mut out = Vec::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            for attachment in &node.attachments {
+                if let Attachment::Mesh(_) = attachment {
+                    out.push(NodeId(i));
+                }
+            }
+        }
+        out
+    }
+
+    // light attachments moved into world space
+    pub fn lights(&self) -> Vec<Light> {
+        let mut out = Vec::new();
+        for node in &self.nodes {
+            for attachment in &node.attachments {
+                if let Attachment::Light(light) = attachment {
+                    out.push(world_light(light, &node.world));
+                }
+            }
+        }
+        out
+    }
+
+    // world matrices of the nodes carrying cameras
+    pub fn cameras(&self) -> Vec<Mat4> {
+        self.nodes
+            .iter()
+            .filter(|n| {
+                n.attachments
+                    .iter()
+                    .any(|a| matches!(a, Attachment::Camera))
+            })
+            .map(|n| n.world)
+            .collect()
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Scene {
+        Scene::new()
+    }
+}
+
+fn world_light(light: &Light, world: &Mat4) -> Light {
+    let point = |p: Vec3| (*world * p.extend(1.0)).xyz();
+    let direction = |d: Vec3| (*world * d.extend(0.0)).xyz().normalize();
+    let kind = match light.kind {
+        LightKind::Directional { direction: d } => LightKind::Directional {
+            direction: direction(d),
+        },
+        LightKind::Point { position, range } => LightKind::Point {
+            position: point(position),
+            range,
+        },
+        LightKind::Spot {
+            position,
+            direction: d,
+            angle,
+            range,
+        } => LightKind::Spot {
+            position: point(position),
+            direction: direction(d),
+            angle,
+            range,
+        },
+    };
+    Light { kind, ..*light }
+}
@@ -0,0 +1,402 @@
+/*
+    shadow maps
+    depth rendered from the light's point of view, orthographic for directional
+    lights and perspective for spots. surfaces compare their own light space
+    depth against it, hard, with percentage closer filtering, or with pcss
+    which widens the filter the further the receiver is from its blocker.
+    point lights render all six directions into a cube map instead
+*/
+
+use crate::framebuffer::Framebuffer;
+use crate::light::{Light, LightKind};
+use crate::mm::{Mat4, Vec3};
+use crate::raster::{draw_indexed, draw_triangle, Fragment, Varying, Vertex};
+use crate::state::{Cull, RenderState};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ShadowFilter {
+    Hard,
+    // averages a (2r + 1) squared block of depth compares
+    Pcf(usize),
+    // light_size is the penumbra width in texels per unit of blocker to receiver distance
+    Pcss {
+        light_size: f32,
+        search_radius: usize,
+        max_radius: f32,
+    },
+}
+
+pub struct ShadowMap {
+    pub size: usize,
+    pub target: Framebuffer,
+    pub view_proj: Mat4,
+    pub orthographic: bool,
+    pub near: f32,
+    pub far: f32,
+    pub filter: ShadowFilter,
+    // polygon offset used while rendering the map, see RenderState
+    pub depth_bias: f32,
+    pub slope_bias: f32,
+}
+
+// fraction of a grid of compares that pass, steps taps out to each side over radius texels
+fn grid<F>(depth: f32, radius: f32, steps: i32, stored: &F) -> f32
+where
+    F: Fn(f32, f32) -> f32,
+{
+    if steps == 0 {
+        return if depth <= stored(0.0, 0.0) { 1.0 } else { 0.0 };
+    }
+    let spacing = radius / steps as f32;
+    let mut lit = 0;
+    for j in -steps..=steps {
+        for i in -steps..=steps {
+            if depth <= stored(i as f32 * spacing, j as f32 * spacing) {
+                lit += 1;
+            }
+        }
+    }
+    lit as f32 / ((2 * steps + 1) * (2 * steps + 1)) as f32
+}
+
+// shared by both kinds of map, stored looks up the map at an offset in texels
+// and linear turns stored values into distance from the light for pcss
+fn filter_shadow<F, L>(
+    filter: ShadowFilter,
+    depth: f32,
+    perspective: bool,
+    stored: F,
+    linear: L,
+) -> f32
+where
+    F: Fn(f32, f32) -> f32,
+    L: Fn(f32) -> f32,
+{
+    match filter {
+        ShadowFilter::Hard => grid(depth, 0.0, 0, &stored),
+        ShadowFilter::Pcf(r) => grid(depth, r as f32, r as i32, &stored),
+        ShadowFilter::Pcss {
+            light_size,
+            search_radius,
+            max_radius,
+        } => {
+            // average depth of whatever is in front of us nearby
+            let r = search_radius as i32;
+            let mut blocker_sum = 0.0;
+            let mut blockers = 0;
+            for j in -r..=r {
+                for i in -r..=r {
+                    let s = stored(i as f32, j as f32);
+                    if s < depth {
+                        blocker_sum += linear(s);
+                        blockers += 1;
+                    }
+                }
+            }
+            if blockers == 0 {
+                return 1.0;
+            }
+            let blocker = blocker_sum / blockers as f32;
+            let receiver = linear(depth);
+            // similar triangles, orthographic lights have no spread with distance
+            let penumbra = if perspective {
+                light_size * (receiver - blocker) / blocker
+            } else {
+                light_size * (receiver - blocker)
+            };
+            grid(depth, penumbra.clamp(1.0, max_radius), 2, &stored)
+        }
+    }
+}
+
+// a light that is looking straight up or down needs another up vector
+fn light_up(direction: Vec3) -> Vec3 {
+    if direction.y.abs() > 0.99 {
+        Vec3::new(0.0, 0.0, 1.0)
+    } else {
+        Vec3::new(0.0, 1.0, 0.0)
+    }
+}
+
+impl ShadowMap {
+    pub fn new(size: usize, filter: ShadowFilter) -> ShadowMap {
+        ShadowMap {
+            size,
+            target: Framebuffer::new(size, size),
+            view_proj: Mat4::identity(),
+            orthographic: true,
+            near: 0.1,
+            far: 100.0,
+            filter,
+            depth_bias: 0.0005,
+            slope_bias: 1.5,
+        }
+    }
+
+    // aim the map at a light, directional lights get fitted around the bounding sphere
+    // point lights need all six directions, they go in a CubeShadowMap
+    pub fn setup(&mut self, light: &Light, center: Vec3, radius: f32) -> Result<(), String> {
+        let (view, proj) = match light.kind {
+            LightKind::Directional { direction } => {
+                let eye = center - direction * (radius * 2.0);
+                self.orthographic = true;
+                self.near = radius;
+                self.far = radius * 3.0;
+                (
+                    Mat4::look_at(eye, center, light_up(direction)),
+                    Mat4::orthographic(-radius, radius, -radius, radius, self.near, self.far),
+                )
+            }
+            LightKind::Spot {
+                position,
+                direction,
+                angle,
+                range,
+            } => {
+                self.orthographic = false;
+                self.near = (range * 0.01).max(0.01);
+                self.far = range;
+                (
+                    Mat4::look_at(position, position + direction, light_up(direction)),
+                    Mat4::perspective(angle, 1.0, self.near, self.far),
+                )
+            }
+            LightKind::Point { .. } => {
+                return Err("point lights need a cube shadow map".to_string());
+            }
+        };
+        self.view_proj = proj * view;
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.target.clear_depth(1.0);
+    }
+
+    // render occluders given in world space, both faces so open meshes still cast
+    pub fn draw(&mut self, world: &[Vec3], indices: &[[usize; 3]]) {
+        let state = RenderState {
+            cull: Cull::None,
+            color_write: false,
+            depth_bias: self.depth_bias,
+            slope_bias: self.slope_bias,
+            ..RenderState::default()
+        };
+        let vertices: Vec<Vertex> = world
+            .iter()
+            .map(|p| Vertex {
+                position: self.view_proj * p.extend(1.0),
+                varying: Varying::new(Vec3::zero()),
+            })
+            .collect();
+        draw_indexed(
+            &mut self.target,
+            &state,
+            &vertices,
+            indices,
+            &mut |_: &Fragment| Some(Vec3::zero()),
+        );
+    }
+
+    fn stored(&self, x: i32, y: i32) -> f32 {
+        let x = x.clamp(0, self.size as i32 - 1) as usize;
+        let y = y.clamp(0, self.size as i32 - 1) as usize;
+        self.target.depth[self.target.index(x, y)]
+    }
+
+    // map depth back to distance from the light
+    fn linear(&self, depth: f32) -> f32 {
+        if self.orthographic {
+            self.near + depth * (self.far - self.near)
+        } else {
+            let ndc = depth * 2.0 - 1.0;
+            2.0 * self.near * self.far / (self.far + self.near - ndc * (self.far - self.near))
+        }
+    }
+
+    // how lit a world space point is, 1 outside the map
+    pub fn visibility(&self, world: Vec3) -> f32 {
+        let clip = self.view_proj * world.extend(1.0);
+        if clip.w <= 0.0 {
+            return 1.0;
+        }
+        let ndc = clip.xyz() / clip.w;
+        let depth = ndc.z * 0.5 + 0.5;
+        if ndc.x.abs() > 1.0 || ndc.y.abs() > 1.0 || depth > 1.0 {
+            return 1.0;
+        }
+        let u = (ndc.x * 0.5 + 0.5) * self.size as f32;
+        let v = (0.5 - ndc.y * 0.5) * self.size as f32;
+
+        let stored =
+            |dx: f32, dy: f32| self.stored((u + dx).floor() as i32, (v + dy).floor() as i32);
+        filter_shadow(self.filter, depth, !self.orthographic, stored, |d| {
+            self.linear(d)
+        })
+    }
+}
+
+// forward and up for +x -x +y -y +z -z, each looking down its axis with a 90 degree frustum
+fn cube_faces() -> [(Vec3, Vec3); 6] {
+    let down = Vec3::new(0.0, -1.0, 0.0);
+    [
+        (Vec3::new(1.0, 0.0, 0.0), down),
+        (Vec3::new(-1.0, 0.0, 0.0), down),
+        (Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)),
+        (Vec3::new(0.0, -1.0, 0.0), Vec3::new(0.0, 0.0, -1.0)),
+        (Vec3::new(0.0, 0.0, 1.0), down),
+        (Vec3::new(0.0, 0.0, -1.0), down),
+    ]
+}
+
+// omnidirectional shadows for point lights
+// every face stores the distance to the closest surface in its color plane,
+// so receivers compare plain distances instead of per face projected depth
+pub struct CubeShadowMap {
+    pub size: usize,
+    pub faces: Vec<Framebuffer>,
+    pub view_proj: [Mat4; 6],
+    pub position: Vec3,
+    pub near: f32,
+    pub far: f32,
+    pub filter: ShadowFilter,
+    // fraction of the receiver's distance, texels get bigger further out
+    pub bias: f32,
+    // texels of extra distance stored per unit of slope away from the light
+    pub slope_bias: f32,
+}
+
+impl CubeShadowMap {
+    pub fn new(size: usize, filter: ShadowFilter) -> CubeShadowMap {
+        CubeShadowMap {
+            size,
+            faces: (0..6).map(|_| Framebuffer::new(size, size)).collect(),
+            view_proj: [Mat4::identity(); 6],
+            position: Vec3::zero(),
+            near: 0.1,
+            far: 100.0,
+            filter,
+            bias: 0.01,
+            slope_bias: 1.5,
+        }
+    }
+
+    pub fn setup(&mut self, light: &Light) -> Result<(), String> {
+        let (position, range) = match light.kind {
+            LightKind::Point { position, range } => (position, range),
+            _ => return Err("cube shadow maps are for point lights".to_string()),
+        };
+        self.position = position;
+        self.near = (range * 0.01).max(0.01);
+        self.far = range;
+        let proj = Mat4::perspective(std::f32::consts::FRAC_PI_2, 1.0, self.near, self.far);
+        for (i, (forward, up)) in cube_faces().into_iter().enumerate() {
+            self.view_proj[i] = proj * Mat4::look_at(position, position + forward, up);
+        }
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        for face in self.faces.iter_mut() {
+            face.clear_color(Vec3::one() * f32::MAX);
+            face.clear_depth(1.0);
+        }
+    }
+
+    // render occluders given in world space into all six faces
+    // surfaces seen at a glancing angle get pushed back further, like a polygon offset
+    pub fn draw(&mut self, world: &[Vec3], indices: &[[usize; 3]]) {
+        let state = RenderState {
+            cull: Cull::None,
+            ..RenderState::default()
+        };
+        let position = self.position;
+        let texel = 2.0 / self.size as f32;
+        let slope_bias = self.slope_bias;
+        let mut shade = |frag: &Fragment| {
+            let to_surface = frag.varying.world - position;
+            let distance = to_surface.length();
+            let cos = (frag.varying.normal.dot(&to_surface) / distance)
+                .abs()
+                .max(0.1);
+            let tan = (1.0 - cos * cos).sqrt() / cos;
+            Some(Vec3::one() * (distance + slope_bias * texel * distance * tan))
+        };
+        for (face, view_proj) in self.faces.iter_mut().zip(self.view_proj.iter()) {
+            for tri in indices {
+                let p = [world[tri[0]], world[tri[1]], world[tri[2]]];
+                let normal = (p[1] - p[0]).cross(&(p[2] - p[0])).normalize();
+                let vertex = |p: Vec3| {
+                    let mut varying = Varying::new(Vec3::zero());
+                    varying.world = p;
+                    varying.normal = normal;
+                    Vertex {
+                        position: *view_proj * p.extend(1.0),
+                        varying,
+                    }
+                };
+                draw_triangle(
+                    face,
+                    &state,
+                    &[vertex(p[0]), vertex(p[1]), vertex(p[2])],
+                    &mut shade,
+                );
+            }
+        }
+    }
+
+    // stored distance in a direction from the light, the major axis picks the face
+    fn lookup(&self, dir: Vec3) -> f32 {
+        let (ax, ay, az) = (dir.x.abs(), dir.y.abs(), dir.z.abs());
+        let face = if ax >= ay && ax >= az {
+            if dir.x > 0.0 {
+                0
+            } else {
+                1
+            }
+        } else if ay >= az {
+            if dir.y > 0.0 {
+                2
+            } else {
+                3
+            }
+        } else if dir.z > 0.0 {
+            4
+        } else {
+            5
+        };
+        let clip = self.view_proj[face] * (self.position + dir).extend(1.0);
+        let x = ((clip.x / clip.w * 0.5 + 0.5) * self.size as f32) as usize;
+        let y = ((0.5 - clip.y / clip.w * 0.5) * self.size as f32) as usize;
+        let target = &self.faces[face];
+        target.color[target.index(x.min(self.size - 1), y.min(self.size - 1))].x
+    }
+
+    // how lit a world space point is, filter taps are offset in direction space
+    // so the ones that fall off a face land on its neighbor instead of clamping
+    pub fn visibility(&self, world: Vec3) -> f32 {
+        let to_world = world - self.position;
+        let distance = to_world.length();
+        if distance >= self.far || distance == 0.0 {
+            return 1.0;
+        }
+        let dir = to_world / distance;
+        let helper = if dir.y.abs() > 0.99 {
+            Vec3::new(1.0, 0.0, 0.0)
+        } else {
+            Vec3::new(0.0, 1.0, 0.0)
+        };
+        let tangent = dir.cross(&helper).normalize();
+        let bitangent = dir.cross(&tangent);
+        // one texel at the middle of a face, at unit distance along the axis
+        let texel = 2.0 / self.size as f32;
+        let stored = |dx: f32, dy: f32| self.lookup(dir + (tangent * dx + bitangent * dy) * texel);
+        filter_shadow(
+            self.filter,
+            distance * (1.0 - self.bias),
+            true,
+            stored,
+            |d| d,
+        )
+    }
+}
@@ -0,0 +1,176 @@
+/*
+    fixed function pipeline state
+    culling, depth and stencil settings used by the rasterizer per draw
+*/
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Compare {
+    Never,
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+impl Compare {
+    // incoming value on the left, stored value on the right, like gl
+    pub fn test<T: PartialOrd>(&self, incoming: T, stored: T) -> bool {
+        match self {
+            Compare::Never => false,
+            Compare::Less => incoming < stored,
+            Compare::Equal => incoming == stored,
+            Compare::LessEqual => incoming <= stored,
+            Compare::Greater => incoming > stored,
+            Compare::NotEqual => incoming != stored,
+            Compare::GreaterEqual => incoming >= stored,
+            Compare::Always => true,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StencilOp {
+    Keep,
+    Zero,
+    Replace,
+    Incr,
+    Decr,
+    Invert,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct StencilState {
+    pub enabled: bool,
+    pub func: Compare,
+    pub reference: u8,
+    pub read_mask: u8,
+    pub write_mask: u8,
+    pub fail: StencilOp,
+    pub depth_fail: StencilOp,
+    pub pass: StencilOp,
+}
+
+impl StencilState {
+    pub fn disabled() -> StencilState {
+        StencilState {
+            enabled: false,
+            func: Compare::Always,
+            reference: 0,
+            read_mask: 0xff,
+            write_mask: 0xff,
+            fail: StencilOp::Keep,
+            depth_fail: StencilOp::Keep,
+            pass: StencilOp::Keep,
+        }
+    }
+
+    // write the reference value wherever the draw lands
+    pub fn write(reference: u8) -> StencilState {
+        StencilState {
+            enabled: true,
+            reference,
+            pass: StencilOp::Replace,
+            ..StencilState::disabled()
+        }
+    }
+
+    // only draw where the stored value compares against the reference
+    pub fn mask(func: Compare, reference: u8) -> StencilState {
+        StencilState {
+            enabled: true,
+            func,
+            reference,
+            ..StencilState::disabled()
+        }
+    }
+
+    pub fn test(&self, stored: u8) -> bool {
+        !self.enabled
+            || self
+                .func
+                .test(self.reference & self.read_mask, stored & self.read_mask)
+    }
+
+    // apply an op to a stored value, only touching the bits in the write mask
+    pub fn apply(&self, op: StencilOp, stored: u8) -> u8 {
+        let value = match op {
+            StencilOp::Keep => stored,
+            StencilOp::Zero => 0,
+            StencilOp::Replace => self.reference,
+            StencilOp::Incr => stored.saturating_add(1),
+            StencilOp::Decr => stored.saturating_sub(1),
+            StencilOp::Invert => !stored,
+        };
+        (stored & !self.write_mask) | (value & self.write_mask)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Cull {
+    None,
+    Back,
+    Front,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct RenderState {
+    pub cull: Cull,
+    pub depth_test: bool,
+    pub depth_write: bool,
+    pub depth_func: Compare,
+    pub color_write: bool,
+    pub stencil: StencilState,
+    // off gives affine, screen space interpolation like the ps1
+    pub perspective_correct: bool,
+    // round projected vertices to whole pixels, no subpixel precision
+    pub snap_vertices: bool,
+    // store how much of each pixel the triangle covers, for the n64 style vi filter
+    pub coverage: bool,
+    // polygon offset, pushes depth back by a constant plus the triangle's depth slope
+    // shadow map passes use it to keep surfaces from shadowing themselves
+    pub depth_bias: f32,
+    pub slope_bias: f32,
+}
+
+impl Default for RenderState {
+    fn default() -> RenderState {
+        RenderState {
+            cull: Cull::Back,
+            depth_test: true,
+            depth_write: true,
+            depth_func: Compare::Less,
+            color_write: true,
+            stencil: StencilState::disabled(),
+            perspective_correct: true,
+            snap_vertices: false,
+            coverage: false,
+            depth_bias: 0.0,
+            slope_bias: 0.0,
+        }
+    }
+}
+
+impl RenderState {
+    // playstation style: wobbly affine textures, snapped vertices and no depth buffer
+    // pair it with ps1::draw_ordered so triangles still sort back to front
+    pub fn ps1() -> RenderState {
+        RenderState {
+            depth_test: false,
+            depth_write: false,
+            perspective_correct: false,
+            snap_vertices: true,
+            ..RenderState::default()
+        }
+    }
+
+    // nintendo 64 style: normal depth buffered drawing plus edge coverage for the vi
+    pub fn n64() -> RenderState {
+        RenderState {
+            coverage: true,
+            ..RenderState::default()
+        }
+    }
+}
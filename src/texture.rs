@@ -0,0 +1,251 @@
+/*
+    textures
+    cpu side images the fragment stage samples from
+    uvs wrap around, v = 0 is the top row
+*/
+
+use crate::mm::{Mat3, Vec2, Vec3, Vec4};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+    // the n64's cheaper bilinear, blends 3 texels picked by which triangle of the quad we are in
+    ThreePoint,
+}
+
+#[derive(Debug, Clone)]
+pub struct Texture {
+    pub width: usize,
+    pub height: usize,
+    pub texels: Vec<Vec3>,
+}
+
+impl Texture {
+    pub fn new(width: usize, height: usize, fill: Vec3) -> Texture {
+        Texture {
+            width,
+            height,
+            texels: vec![fill; width * height],
+        }
+    }
+
+    // rgba8 rows top first, like raylib's image data
+    pub fn from_rgba8(width: usize, height: usize, pixels: &[u8]) -> Texture {
+        let texels = pixels
+            .chunks(4)
+            .map(|p| Vec3::new(p[0] as f32, p[1] as f32, p[2] as f32) / 255.0)
+            .collect();
+        Texture {
+            width,
+            height,
+            texels,
+        }
+    }
+
+    pub fn checkerboard(size: usize, cells: usize, a: Vec3, b: Vec3) -> Texture {
+        let mut texture = Texture::new(size, size, a);
+        let cell = (size / cells).max(1);
+        for y in 0..size {
+            for x in 0..size {
+                if (x / cell + y / cell) % 2 == 1 {
+                    texture.texels[y * size + x] = b;
+                }
+            }
+        }
+        texture
+    }
+
+    // radiance rgbe, the usual format for hdr environment maps
+    // handles flat and new style run length encoded scanlines
+    pub fn parse_hdr(bytes: &[u8]) -> Result<Texture, String> {
+        if !bytes.starts_with(b"#?") {
+            return Err("not a radiance hdr file".to_string());
+        }
+        // header lines up to a blank one, then the resolution line
+        let mut pos = 0;
+        let line = |pos: &mut usize| -> Result<String, String> {
+            let start = *pos;
+            while *pos < bytes.len() && bytes[*pos] != b'\n' {
+                *pos += 1;
+            }
+            if *pos >= bytes.len() {
+                return Err("hdr header ends early".to_string());
+            }
+            *pos += 1;
+            Ok(String::from_utf8_lossy(&bytes[start..*pos - 1]).to_string())
+        };
+        loop {
+            let l = line(&mut pos)?;
+            if l.is_empty() {
+                break;
+            }
+            if let Some(format) = l.strip_prefix("FORMAT=") {
+                if format != "32-bit_rle_rgbe" {
+                    return Err(format!("unsupported hdr format {}", format));
+                }
+            }
+        }
+        let resolution = line(&mut pos)?;
+        let parts: Vec<&str> = resolution.split_whitespace().collect();
+        if parts.len() != 4 || parts[0] != "-Y" || parts[2] != "+X" {
+            return Err(format!("unsupported hdr orientation {}", resolution));
+        }
+        let height: usize = parts[1].parse().map_err(|_| "bad hdr height")?;
+        let width: usize = parts[3].parse().map_err(|_| "bad hdr width")?;
+
+        let mut texels = Vec::with_capacity(width * height);
+        let mut scanline = vec![[0u8; 4]; width];
+        let truncated = || "hdr data ends early".to_string();
+        for _ in 0..height {
+            let rle = (8..0x8000).contains(&width)
+                && bytes.get(pos..pos + 4).is_some_and(|b| {
+                    b[0] == 2 && b[1] == 2 && ((b[2] as usize) << 8 | b[3] as usize) == width
+                });
+            if rle {
+                pos += 4;
+                // each channel is stored separately as runs and literal spans
+                for channel in 0..4 {
+                    let mut values = Vec::with_capacity(width);
+                    while values.len() < width {
+                        let count = *bytes.get(pos).ok_or_else(truncated)? as usize;
+                        pos += 1;
+                        if count > 128 {
+                            let value = *bytes.get(pos).ok_or_else(truncated)?;
+                            pos += 1;
+                            let n = (count - 128).min(width - values.len());
+                            values.extend(std::iter::repeat_n(value, n));
+                        } else {
+                            if count == 0 {
+                                return Err("bad hdr run".to_string());
+                            }
+                            let n = count.min(width - values.len());
+                            values
+                                .extend_from_slice(bytes.get(pos..pos + n).ok_or_else(truncated)?);
+                            pos += count;
+                        }
+                    }
+                    for (texel, value) in scanline.iter_mut().zip(values) {
+                        texel[channel] = value;
+                    }
+                }
+            } else {
+                for texel in scanline.iter_mut() {
+                    let b = bytes.get(pos..pos + 4).ok_or_else(truncated)?;
+                    texel.copy_from_slice(b);
+                    pos += 4;
+                }
+            }
+            for &[r, g, b, e] in &scanline {
+                texels.push(if e == 0 {
+                    Vec3::zero()
+                } else {
+                    let scale = 2f32.powi(e as i32 - 136);
+                    Vec3::new(r as f32, g as f32, b as f32) * scale
+                });
+            }
+        }
+        Ok(Texture {
+            width,
+            height,
+            texels,
+        })
+    }
+
+    pub fn load_hdr(path: &str) -> Result<Texture, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+        Texture::parse_hdr(&bytes).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    // texel lookup with wrapping
+    pub fn texel(&self, x: i32, y: i32) -> Vec3 {
+        let x = x.rem_euclid(self.width as i32) as usize;
+        let y = y.rem_euclid(self.height as i32) as usize;
+        self.texels[y * self.width + x]
+    }
+
+    pub fn sample_nearest(&self, uv: Vec2) -> Vec3 {
+        let x = (uv.x * self.width as f32).floor() as i32;
+        let y = (uv.y * self.height as f32).floor() as i32;
+        self.texel(x, y)
+    }
+
+    pub fn sample_bilinear(&self, uv: Vec2) -> Vec3 {
+        let fx = uv.x * self.width as f32 - 0.5;
+        let fy = uv.y * self.height as f32 - 0.5;
+        let x0 = fx.floor();
+        let y0 = fy.floor();
+        let tx = fx - x0;
+        let ty = fy - y0;
+        let (x0, y0) = (x0 as i32, y0 as i32);
+        let top = self.texel(x0, y0).lerp(&self.texel(x0 + 1, y0), tx);
+        let bottom = self.texel(x0, y0 + 1).lerp(&self.texel(x0 + 1, y0 + 1), tx);
+        top.lerp(&bottom, ty)
+    }
+
+    pub fn sample_three_point(&self, uv: Vec2) -> Vec3 {
+        let fx = uv.x * self.width as f32 - 0.5;
+        let fy = uv.y * self.height as f32 - 0.5;
+        let x0 = fx.floor();
+        let y0 = fy.floor();
+        let tx = fx - x0;
+        let ty = fy - y0;
+        let (x0, y0) = (x0 as i32, y0 as i32);
+        let t01 = self.texel(x0, y0 + 1);
+        let t10 = self.texel(x0 + 1, y0);
+        if tx + ty <= 1.0 {
+            let t00 = self.texel(x0, y0);
+            t00 + (t10 - t00) * tx + (t01 - t00) * ty
+        } else {
+            let t11 = self.texel(x0 + 1, y0 + 1);
+            t11 + (t01 - t11) * (1.0 - tx) + (t10 - t11) * (1.0 - ty)
+        }
+    }
+
+    pub fn sample(&self, uv: Vec2, filter: Filter) -> Vec3 {
+        match filter {
+            Filter::Nearest => self.sample_nearest(uv),
+            Filter::Bilinear => self.sample_bilinear(uv),
+            Filter::ThreePoint => self.sample_three_point(uv),
+        }
+    }
+
+    // treats this as a tangent space normal map and bends the interpolated normal with it
+    // normal and tangent come straight from the varyings, the bitangent is rebuilt
+    // per pixel from them like mikktspace expects. scale flattens or deepens the bumps
+    pub fn sample_normal(
+        &self,
+        uv: Vec2,
+        filter: Filter,
+        normal: Vec3,
+        tangent: Vec4,
+        scale: f32,
+    ) -> Vec3 {
+        let t = tangent.xyz();
+        if t.dot(&t) == 0.0 {
+            return normal.normalize();
+        }
+        let bitangent = normal.cross(&t) * tangent.w;
+        let tbn = Mat3::from_cols(t, bitangent, normal);
+        let texel = self.sample(uv, filter) * 2.0 - Vec3::one();
+        let bumped = Vec3::new(texel.x * scale, texel.y * scale, texel.z);
+        (tbn * bumped).normalize()
+    }
+
+    // flat normal map from a height field, for when there is no baked one
+    // strength is how many texels tall a full white step is
+    pub fn normal_map_from_height(&self, strength: f32) -> Texture {
+        let mut out = Texture::new(self.width, self.height, Vec3::zero());
+        let height = |x: i32, y: i32| self.texel(x, y).dot(&Vec3::one()) / 3.0;
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                let dx = (height(x + 1, y) - height(x - 1, y)) * 0.5 * strength;
+                // tangent space y follows increasing v, down the image
+                let dy = (height(x, y + 1) - height(x, y - 1)) * 0.5 * strength;
+                let n = Vec3::new(-dx, -dy, 1.0).normalize();
+                out.texels[y as usize * self.width + x as usize] = 0.5 + n * 0.5;
+            }
+        }
+        out
+    }
+}